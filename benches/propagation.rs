@@ -0,0 +1,97 @@
+//! Benchmarks the propagation schemes on the flow past a cylinder in a
+//! channel of `src/main.rs`.
+//!
+//! The throughput is reported as `MB/s`, which here are million lattice
+//! updates per second (MLUPS): every iteration counts one "byte" per cell.
+#![feature(test)]
+
+extern crate lbm;
+extern crate test;
+
+use lbm::*;
+use test::Bencher;
+
+type Dist = distribution::D2Q9;
+type Col = physics::ns::SingleRelaxationTime;
+type Physics = physics::NavierStokes<Dist, Col>;
+
+const INFLOW_VELOCITY: f64 = 0.05;
+
+/// Grid size, whose populations do not fit in the caches
+const L_X: usize = 1200;
+const L_Y: usize = 600;
+
+/// Iterations per benchmark iteration
+const N_IT: usize = 5;
+
+type Grid = grid::StructuredRectangular;
+
+fn channel<T: Float>(
+    propagation: Propagation,
+) -> Solver<Physics, Grid, layout::AoS, T> {
+    let grid = grid::StructuredRectangular::new(L_X, L_Y)
+        .with_periodic([false, false]);
+    let physics: Physics = Physics::new(0.1, 0.015, Col { omega: 1.85 });
+    let mut s = Solver::new(grid, physics).with_propagation(propagation);
+    s.add_condition(Box::new(boundary::Condition::new(
+        boundary::Type::InterpolatedBounceBack(
+            boundary::Interpolation::Bouzidi,
+        ),
+        geometry::Circle::new(grid.x, grid.y),
+    )));
+    s.add_condition(Box::new(
+        boundary::Condition::with_velocity(
+            geometry::Plane::new((1, 0), (0, 0)),
+            |_, _| [INFLOW_VELOCITY, 0., 0.],
+        ),
+    ));
+    s.add_condition(Box::new(boundary::Condition::new(
+        boundary::Type::Convective(INFLOW_VELOCITY),
+        geometry::Plane::new((-1, 0), (grid.x - 1, 0)),
+    )));
+    s.add_condition(Box::new(boundary::Condition::new(
+        boundary::Type::BounceBack,
+        geometry::Plane::new((0, 1), (0, 0)),
+    )));
+    s.add_condition(Box::new(boundary::Condition::new(
+        boundary::Type::BounceBack,
+        geometry::Plane::new((0, -1), (0, grid.y - 1)),
+    )));
+    s.initialize(|_| {
+        let mut ns = DistributionStorage::<Dist, T>::default();
+        for n in Dist::all() {
+            ns.as_mut()[n.value()] =
+                T::from_f64(physics.inflow_density * n.constant());
+        }
+        ns
+    });
+    s
+}
+
+fn bench<T: Float>(b: &mut Bencher, propagation: Propagation) {
+    let mut s = channel::<T>(propagation);
+    // one "byte" per lattice update: the `MB/s` reported by the bencher
+    // are MLUPS, not memory traffic
+    b.bytes = (L_X * L_Y * N_IT) as u64;
+    b.iter(|| s.run(N_IT, 0));
+}
+
+#[bench]
+fn two_arrays(b: &mut Bencher) {
+    bench::<f64>(b, Propagation::TwoArrays);
+}
+
+#[bench]
+fn swap(b: &mut Bencher) {
+    bench::<f64>(b, Propagation::Swap);
+}
+
+#[bench]
+fn fused(b: &mut Bencher) {
+    bench::<f64>(b, Propagation::Fused);
+}
+
+#[bench]
+fn fused_single_precision(b: &mut Bencher) {
+    bench::<f32>(b, Propagation::Fused);
+}
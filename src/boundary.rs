@@ -1,23 +1,75 @@
 use geometry::Geometry;
-use grid;
-use num;
+use grid::{self, Grid};
+use Float;
 
 #[derive(Copy, Clone, PartialEq, PartialOrd)]
 pub enum Type {
     BounceBack,
-    Inflow(num, num),
+    /// Bounce-back of a wall moving with the given velocity
+    MovingWall([f64; 3]),
+    /// Bounce-back interpolated to the exact position of the wall along
+    /// each link
+    InterpolatedBounceBack(Interpolation),
+    /// Specular reflection of the populations about the normal of a
+    /// straight wall (free-slip walls and symmetry planes)
+    Specular,
+    Inflow(f64, f64),
+    /// Zou-He velocity boundary on a straight wall (the velocity is the one
+    /// of `Condition::with_velocity`, zero otherwise)
+    Velocity,
+    /// Zou-He pressure boundary on a straight wall with the given density
+    Pressure(f64),
+    /// Non-equilibrium extrapolation velocity boundary (the velocity is the
+    /// one of `Condition::new_with_velocity`, zero otherwise)
+    ExtrapolatedVelocity,
+    /// Non-equilibrium extrapolation pressure boundary with the given
+    /// density
+    ExtrapolatedPressure(f64),
+    /// Outflow copying the populations entering the fluid from the fluid
+    /// neighbor
+    ZeroGradient,
+    /// Convective (Orlanski-type) outflow `df/dt + U df/dn = 0` with the
+    /// given convective velocity `U` (e.g. the mean outflow velocity)
+    Convective(f64),
 }
 
+/// Interpolation scheme of `Type::InterpolatedBounceBack`.
+///
+/// With `q` the fraction of the link from the fluid cell `x_f` to the solid
+/// cell at which it crosses the wall, the population `f_i` leaving the
+/// wall towards `x_f` is interpolated from the post-collision populations
+/// `f_i'` of `x_f` and `x_ff = x_f + c_i` and the population `f_-i'` of
+/// `x_f` moving towards the wall.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub enum Interpolation {
+    /// Bouzidi, Firdaouss and Lallemand (2001):
+    /// `f_i = 2 q f_-i'(x_f) + (1 - 2 q) f_-i'(x_ff)` if `q < 1 / 2`,
+    /// `f_i = f_-i'(x_f) / (2 q) + (2 q - 1) / (2 q) f_i'(x_f)` otherwise
+    Bouzidi,
+    /// Yu, Mei, Luo and Shyy (2003):
+    /// `f_i = (q f_i'(x_f) + q f_-i'(x_f) + (1 - q) f_-i'(x_ff)) / (1 + q)`
+    Yu,
+}
+
+/// Velocity type of conditions without a prescribed velocity.
+pub type NoVelocity = fn(grid::X, usize) -> [f64; 3];
+
 pub trait AnyCondition: Send + Sync {
-    #[inline(always)]
     fn condition(&self) -> Type;
-    #[inline(always)]
     fn contains(&self, grid::X) -> bool;
+    /// Unit normal pointing into the fluid at `x` (if any)
+    fn normal(&self, x: grid::X) -> Option<[i8; 3]>;
+    /// Prescribed velocity at `x` during the iteration `n_it`
+    fn velocity(&self, x: grid::X, n_it: usize) -> [f64; 3];
+    /// Fraction of the link from `x` to `x + c` at which it enters the
+    /// geometry (if it does)
+    fn intersection(&self, x: grid::X, c: [i8; 3]) -> Option<f64>;
 }
 
-pub struct Condition<T: Geometry + Send + Sync> {
+pub struct Condition<T: Geometry + Send + Sync, V = NoVelocity> {
     condition: Type,
     geometry: T,
+    velocity: Option<V>,
 }
 
 impl<T: Geometry + Send + Sync> Condition<T> {
@@ -25,11 +77,37 @@ impl<T: Geometry + Send + Sync> Condition<T> {
         Condition {
             condition: c,
             geometry: g,
+            velocity: None,
+        }
+    }
+}
+
+impl<T, V> Condition<T, V>
+where
+    T: Geometry + Send + Sync,
+    V: Fn(grid::X, usize) -> [f64; 3] + Send + Sync,
+{
+    /// Zou-He velocity boundary on the geometry `g` with the velocity
+    /// `u(x, n_it)` at the cell `x` during the iteration `n_it`.
+    pub fn with_velocity(g: T, u: V) -> Condition<T, V> {
+        Condition::new_with_velocity(Type::Velocity, g, u)
+    }
+    /// Condition `c` on the geometry `g` prescribing the velocity
+    /// `u(x, n_it)` at the cell `x` during the iteration `n_it`.
+    pub fn new_with_velocity(c: Type, g: T, u: V) -> Condition<T, V> {
+        Condition {
+            condition: c,
+            geometry: g,
+            velocity: Some(u),
         }
     }
 }
 
-impl<T: Geometry + Send + Sync> AnyCondition for Condition<T> {
+impl<T, V> AnyCondition for Condition<T, V>
+where
+    T: Geometry + Send + Sync,
+    V: Fn(grid::X, usize) -> [f64; 3] + Send + Sync,
+{
     #[inline(always)]
     fn condition(&self) -> Type {
         self.condition
@@ -38,6 +116,237 @@ impl<T: Geometry + Send + Sync> AnyCondition for Condition<T> {
     fn contains(&self, x: grid::X) -> bool {
         self.geometry.contains(x)
     }
+    #[inline(always)]
+    fn normal(&self, x: grid::X) -> Option<[i8; 3]> {
+        self.geometry.normal(x)
+    }
+    #[inline(always)]
+    fn velocity(&self, x: grid::X, n_it: usize) -> [f64; 3] {
+        match self.velocity {
+            Some(ref u) => u(x, n_it),
+            None => [0., 0., 0.],
+        }
+    }
+    #[inline(always)]
+    fn intersection(&self, x: grid::X, c: [i8; 3]) -> Option<f64> {
+        self.geometry.intersection(x, c)
+    }
+}
+
+/// Density and velocity of the populations `f`.
+#[inline(always)]
+fn moments<T: Float, D: ::Distribution>(f: &[T]) -> (T, [T; 3]) {
+    let mut rho = T::default();
+    let mut m = [T::default(); 3];
+    for n in D::all() {
+        let c = n.direction().num_array();
+        rho += f[n.value()];
+        for d in 0..3 {
+            m[d] += T::from_f64(c[d]) * f[n.value()];
+        }
+    }
+    (rho, [m[0] / rho, m[1] / rho, m[2] / rho])
+}
+
+/// Macroscopic quantity prescribed by a Zou-He boundary.
+#[derive(Copy, Clone)]
+enum ZouHe {
+    Velocity([f64; 3]),
+    Density(f64),
+}
+
+/// Reconstructs the populations `f` entering the fluid through a straight
+/// wall with the inward unit normal `normal` (Zou-He non-equilibrium
+/// bounce-back).
+///
+/// The unknown populations are the bounced-back ones plus the difference of
+/// their equilibria, `f_i = f_-i + 2 w_i rho (c_i . u) / c_s^2`, corrected
+/// along the wall such that the tangential momentum is `rho u`. The missing
+/// density (velocity) follows from mass conservation; pressure boundaries
+/// have no tangential velocity.
+#[inline(always)]
+fn zou_he<T: Float, D: ::Distribution>(
+    f: &mut [T],
+    normal: [i8; 3],
+    bc: ZouHe,
+) {
+    let c_n = |n: D| {
+        let c = n.direction().array();
+        c[0] * normal[0] + c[1] * normal[1] + c[2] * normal[2]
+    };
+    let t = T::from_f64;
+
+    // density (velocity) from the known populations:
+    let mut rho_t = T::default();
+    for n in D::all() {
+        match c_n(n) {
+            0 => rho_t += f[n.value()],
+            c if c < 0 => rho_t += t(2.) * f[n.value()],
+            _ => {}
+        }
+    }
+    let (rho, u) = match bc {
+        ZouHe::Velocity(u) => {
+            let u = [t(u[0]), t(u[1]), t(u[2])];
+            let n = normal;
+            let u_n = u[0] * t(f64::from(n[0])) + u[1] * t(f64::from(n[1])) +
+                u[2] * t(f64::from(n[2]));
+            (rho_t / (t(1.) - u_n), u)
+        }
+        ZouHe::Density(rho) => {
+            let rho = t(rho);
+            let u_n = t(1.) - rho_t / rho;
+            let n = normal;
+            (
+                rho,
+                [
+                    u_n * t(f64::from(n[0])),
+                    u_n * t(f64::from(n[1])),
+                    u_n * t(f64::from(n[2])),
+                ],
+            )
+        }
+    };
+
+    // non-equilibrium bounce-back of the unknown populations:
+    for n in D::all() {
+        if c_n(n) > 0 {
+            let c = n.direction().num_array();
+            let c_u = t(c[0]) * u[0] + t(c[1]) * u[1] + t(c[2]) * u[2];
+            f[n.value()] = f[n.opposite().value()] +
+                t(2.) * t(n.constant()) * rho * c_u / t(D::c_squ());
+        }
+    }
+
+    // tangential momentum correction:
+    let mut lambda = [T::default(); 3];
+    for a in 0..3 {
+        if normal[a] != 0 {
+            continue;
+        }
+        let (mut m, mut k) = (T::default(), T::default());
+        for n in D::all() {
+            let c = t(n.direction().num_array()[a]);
+            m += c * f[n.value()];
+            if c_n(n) > 0 {
+                k += c * c;
+            }
+        }
+        if k > T::default() {
+            lambda[a] = (m - rho * u[a]) / k;
+        }
+    }
+    for n in D::all() {
+        if c_n(n) > 0 {
+            let c = n.direction().num_array();
+            f[n.value()] -= t(c[0]) * lambda[0] + t(c[1]) * lambda[1] +
+                t(c[2]) * lambda[2];
+        }
+    }
+}
+
+/// Material of a grid cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Flag {
+    Fluid,
+    /// Fluid cell of the boundary condition with the given index
+    Boundary(usize),
+    /// Solid cell of the boundary condition with the given index
+    Solid(usize),
+}
+
+impl Flag {
+    /// Index of the (first) boundary condition containing the cell
+    #[inline(always)]
+    pub fn idx(&self) -> Option<usize> {
+        match *self {
+            Flag::Fluid => None,
+            Flag::Boundary(k) | Flag::Solid(k) => Some(k),
+        }
+    }
+    #[inline(always)]
+    pub fn solid(&self) -> bool {
+        match *self {
+            Flag::Solid(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Position in `Flags` of the cells not contained by any condition.
+const NO_CELL: u32 = ::std::u32::MAX;
+
+/// Flags of the cells of a grid and the list of cells contained by
+/// boundary conditions, precomputed from the boundary conditions.
+pub struct Flags {
+    flags: Vec<Flag>,
+    cells: Vec<grid::Idx>,
+    /// Position of each cell in `cells` (`NO_CELL` if not contained)
+    index: Vec<u32>,
+    /// The indices of the conditions containing `cells[i]`, in order, are
+    /// `conditions[offsets[i]..offsets[i + 1]]`
+    offsets: Vec<usize>,
+    conditions: Vec<usize>,
+}
+
+impl Flags {
+    /// Flags of a grid of `size` fluid cells
+    pub fn fluid(size: usize) -> Flags {
+        Flags {
+            flags: vec![Flag::Fluid; size],
+            cells: Vec::new(),
+            index: vec![NO_CELL; size],
+            offsets: vec![0],
+            conditions: Vec::new(),
+        }
+    }
+    /// Flag of the cell `c`
+    #[inline(always)]
+    pub fn flag(&self, c: grid::Idx) -> Flag {
+        self.flags[c.0]
+    }
+    /// Number of cells contained by boundary conditions
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+    /// The `i`-th cell contained by boundary conditions and the indices of
+    /// the conditions containing it
+    #[inline(always)]
+    pub fn cell(&self, i: usize) -> (grid::Idx, &[usize]) {
+        let conditions = &self.conditions[self.offsets[i]..self.offsets[i + 1]];
+        (self.cells[i], conditions)
+    }
+    /// Indices of the conditions containing the cell `c`
+    #[inline(always)]
+    pub fn conditions(&self, c: grid::Idx) -> &[usize] {
+        match self.index[c.0] {
+            NO_CELL => &[],
+            i => self.cell(i as usize).1,
+        }
+    }
+}
+
+/// Population `n` streamed into a cell from outside of the end of a
+/// non-periodic grid axis given the post-collision populations `f` of the
+/// cell.
+///
+/// The conditions containing the cell reconstruct it when they are applied.
+/// Until then, and at cells without conditions, it is the bounced-back
+/// post-collision population `f_-n`: the grid is closed by a no-slip wall
+/// half-way beyond its last cells.
+#[inline(always)]
+pub fn missing<T, F, IF, D>(f: &F, idx_f: IF, n: D) -> T
+where
+    T: Float,
+    IF: Fn(&F, D) -> T,
+    D: ::Distribution,
+{
+    idx_f(f, n.opposite())
 }
 
 #[derive(Default)]
@@ -50,11 +359,27 @@ impl Handler {
         self.boundary_conditions.push(bc);
     }
 
+    /// Number of boundary conditions
+    pub fn len(&self) -> usize {
+        self.boundary_conditions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boundary_conditions.is_empty()
+    }
+
     #[inline(always)]
     pub fn solid_boundary(&self, x: grid::X) -> bool {
         for bc in &self.boundary_conditions {
-            if bc.contains(x) && bc.condition() == Type::BounceBack {
-                return true;
+            if !bc.contains(x) {
+                continue;
+            }
+            match bc.condition() {
+                Type::BounceBack |
+                Type::MovingWall(_) |
+                Type::InterpolatedBounceBack(_) |
+                Type::Specular => return true,
+                _ => {}
             }
         }
         false
@@ -70,31 +395,235 @@ impl Handler {
         None
     }
 
+    /// Evaluates the flags of the cells of `grid`.
+    pub fn flags<G: Grid>(&self, grid: &G) -> Flags {
+        let mut flags = Flags::fluid(grid.size());
+        assert!(grid.size() < NO_CELL as usize);
+        for c in grid.ids() {
+            let x = grid.x(c);
+            let start = flags.conditions.len();
+            for (k, bc) in self.boundary_conditions.iter().enumerate() {
+                if bc.contains(x) {
+                    flags.conditions.push(k);
+                }
+            }
+            if flags.conditions.len() == start {
+                continue;
+            }
+            let k = flags.conditions[start];
+            flags.flags[c.0] = if self.solid_boundary(x) {
+                Flag::Solid(k)
+            } else {
+                Flag::Boundary(k)
+            };
+            flags.index[c.0] = flags.cells.len() as u32;
+            flags.cells.push(c);
+            flags.offsets.push(flags.conditions.len());
+        }
+        flags
+    }
+
+    /// Population `n` leaving the solid cell `x_b` of the condition `bc`
+    /// by interpolated bounce-back.
+    ///
+    /// The streamed populations `f_hlp` of a cell are the post-collision
+    /// populations of its upstream neighbors.
+    #[inline(always)]
+    fn interpolated_bounce_back<T, H, IH, N, FL, D>(
+        &self,
+        bc: &dyn AnyCondition,
+        interpolation: Interpolation,
+        f_hlp: &H,
+        idx_h: &IH,
+        neighbor: &N,
+        flag: &FL,
+        x_b: grid::X,
+        n: D,
+    ) -> T
+    where
+        T: Float,
+        IH: Fn(&H, grid::X, D) -> T,
+        N: Fn(grid::X, D) -> Option<grid::X>,
+        FL: Fn(grid::X) -> Flag,
+        D: ::Distribution,
+    {
+        let i = n.opposite();
+        // f_-i'(x_f):
+        let f_i = idx_h(f_hlp, x_b, i);
+        let x_f = match neighbor(x_b, n) {
+            Some(x_f) if !flag(x_f).solid() => x_f,
+            _ => return f_i,
+        };
+        let x_ff = match neighbor(x_f, n) {
+            Some(x_ff) => x_ff,
+            None => return f_i,
+        };
+        let q = match bc.intersection(x_f, i.direction().array()) {
+            Some(q) => q,
+            None => return f_i,
+        };
+        // f_-i'(x_ff) and f_i'(x_f):
+        let f_ff = idx_h(f_hlp, x_f, i);
+        let f_o = idx_h(f_hlp, x_ff, n);
+        let t = T::from_f64;
+        match interpolation {
+            Interpolation::Bouzidi => if q < 0.5 {
+                if flag(x_ff).solid() {
+                    f_i
+                } else {
+                    t(2. * q) * f_i + t(1. - 2. * q) * f_ff
+                }
+            } else {
+                (f_i + t(2. * q - 1.) * f_o) / t(2. * q)
+            },
+            Interpolation::Yu => if flag(x_ff).solid() {
+                f_i
+            } else {
+                (t(q) * f_o + t(q) * f_i + t(1. - q) * f_ff) / t(1. + q)
+            },
+        }
+    }
+
+    /// Direction of the fluid neighbor of the cell `x` of the condition
+    /// `bc`: the normal of `bc` or, if the neighbor along it is part of a
+    /// boundary (e.g. at corners), the first direction whose neighbor is
+    /// not.
+    ///
+    /// Panics if all neighbors are part of a boundary.
+    #[inline(always)]
+    fn fluid_neighbor<N, FL, D>(
+        &self,
+        bc: &dyn AnyCondition,
+        x: grid::X,
+        neighbor: &N,
+        flag: &FL,
+    ) -> D
+    where
+        N: Fn(grid::X, D) -> Option<grid::X>,
+        FL: Fn(grid::X) -> Flag,
+        D: ::Distribution,
+    {
+        let is_fluid = |n: D| {
+            neighbor(x, n).map_or(false, |x_n| flag(x_n) == Flag::Fluid)
+        };
+        if let Some(normal) = bc.normal(x) {
+            let n = D::all().find(|n| n.direction().array() == normal);
+            if let Some(n) = n {
+                if is_fluid(n) {
+                    return n;
+                }
+            }
+        }
+        D::all()
+            .find(|&n| is_fluid(n))
+            .expect("the boundary cell has no fluid neighbor")
+    }
+
+    /// Applies the boundary `conditions` containing the cell `x` during the
+    /// iteration `n_it` to its post-collision populations `f`.
+    ///
+    /// `f_hlp` are the streamed (pre-collision) populations of all cells,
+    /// `neighbor(x, n)` is the neighbor of `x` in the direction `n` (if it
+    /// is part of the grid), `flag(x)` is the flag of the cell `x`,
+    /// `collision` collides populations reconstructed from `f_hlp`, and
+    /// `equilibrium(rho, u)` are the equilibrium populations of the physics.
     #[inline(always)]
-    pub fn apply<F, H, IF, IH, D>(
+    pub fn apply<T, F, H, IF, IH, N, FL, C, E, D>(
         &self,
+        conditions: &[usize],
         f: &F,
         f_hlp: &H,
         idx_f: IF,
         idx_h: IH,
+        neighbor: N,
+        flag: FL,
+        collision: C,
+        equilibrium: E,
         x: grid::X,
-    ) -> Option<D::Storage>
+        n_it: usize,
+    ) -> Option<D::Storage<T>>
     where
-        IF: Fn(&F, D) -> num,
-        IH: Fn(&H, D) -> num,
+        T: Float,
+        IF: Fn(&F, D) -> T,
+        IH: Fn(&H, grid::X, D) -> T,
+        N: Fn(grid::X, D) -> Option<grid::X>,
+        FL: Fn(grid::X) -> Flag,
+        C: Fn(&D::Storage<T>) -> D::Storage<T>,
+        E: Fn(T, [T; 3]) -> D::Storage<T>,
         D: ::Distribution,
     {
-        let mut r: Option<D::Storage> = None;
+        let mut r: Option<D::Storage<T>> = None;
+        let t = T::from_f64;
 
-        for bc in &self.boundary_conditions {
-            if !bc.contains(x) {
-                continue;
-            }
+        for &k in conditions {
+            let bc = &self.boundary_conditions[k];
             match bc.condition() {
                 Type::BounceBack => {
                     let mut s = D::Storage::default();
                     for n in D::all() {
-                        s.as_mut()[n.value()] = idx_h(f_hlp, n.opposite());
+                        s.as_mut()[n.value()] = idx_h(f_hlp, x, n.opposite());
+                    }
+                    r = Some(s);
+                }
+                Type::MovingWall(u) => {
+                    // bounce-back plus the momentum transferred by the wall,
+                    // `2 w_i rho (c_i . u) / c_s^2`, with the density `rho`
+                    // of the fluid cell the population streams into:
+                    let mut s = D::Storage::default();
+                    for n in D::all() {
+                        let f_i = idx_h(f_hlp, x, n.opposite());
+                        let x_f = match neighbor(x, n) {
+                            Some(x_f) if !flag(x_f).solid() => x_f,
+                            _ => {
+                                s.as_mut()[n.value()] = f_i;
+                                continue;
+                            }
+                        };
+                        let mut rho = T::default();
+                        for m in D::all() {
+                            rho += idx_h(f_hlp, x_f, m);
+                        }
+                        let c = n.direction().num_array();
+                        let c_u = c[0] * u[0] + c[1] * u[1] + c[2] * u[2];
+                        s.as_mut()[n.value()] = f_i +
+                            t(2. * n.constant()) * rho * t(c_u / D::c_squ());
+                    }
+                    r = Some(s);
+                }
+                Type::Specular => {
+                    let normal = bc.normal(x)
+                        .expect("specular walls require a straight wall");
+                    let mut s = D::Storage::default();
+                    for n in D::all() {
+                        // the population leaving in the direction `n` is
+                        // the one that arrived in its mirror image:
+                        let c = n.direction().array();
+                        let c_n = c[0] * normal[0] + c[1] * normal[1] +
+                            c[2] * normal[2];
+                        let mut m = c;
+                        for d in 0..3 {
+                            m[d] -= 2 * c_n * normal[d];
+                        }
+                        let m = D::all()
+                            .find(|m_| m_.direction().array() == m)
+                            .unwrap();
+                        s.as_mut()[n.value()] = idx_h(f_hlp, x, m);
+                    }
+                    r = Some(s);
+                }
+                Type::InterpolatedBounceBack(interpolation) => {
+                    let mut s = D::Storage::default();
+                    for n in D::all() {
+                        s.as_mut()[n.value()] = self.interpolated_bounce_back(
+                            &**bc,
+                            interpolation,
+                            f_hlp,
+                            &idx_h,
+                            &neighbor,
+                            &flag,
+                            x,
+                            n,
+                        );
                     }
                     r = Some(s);
                 }
@@ -109,17 +638,18 @@ impl Handler {
                             s
                         }
                     };
+                    let zero = T::default();
                     {
                         let s = s_.as_mut();
 
                         use geometry::Direction::*;
                         for n in D::all() {
-                            let t = density * accel * n.constant();
+                            let t = t(density * accel * n.constant());
                             match n.direction() {
                                 W => if s
                                     [D::from_direction(W).unwrap().value()] -
                                     t >
-                                    0.
+                                    zero
                                 {
                                     s[D::from_direction(E)
                                             .unwrap()
@@ -131,7 +661,7 @@ impl Handler {
                                 NW => if s
                                     [D::from_direction(NW).unwrap().value()] -
                                     t >
-                                    0.
+                                    zero
                                 {
                                     s[D::from_direction(SE)
                                           .unwrap()
@@ -143,7 +673,7 @@ impl Handler {
                                 SW => if s
                                     [D::from_direction(SW).unwrap().value()] -
                                     t >
-                                    0.
+                                    zero
                                 {
                                     s[D::from_direction(NE)
                                           .unwrap()
@@ -158,8 +688,162 @@ impl Handler {
                     }
                     r = Some(s_);
                 }
+                Type::Velocity | Type::Pressure(_) => {
+                    let normal = bc.normal(x).expect(
+                        "Zou-He boundaries require a straight wall",
+                    );
+                    let prescribed = match bc.condition() {
+                        Type::Pressure(rho) => ZouHe::Density(rho),
+                        _ => ZouHe::Velocity(bc.velocity(x, n_it)),
+                    };
+                    let mut s = D::Storage::default();
+                    for n in D::all() {
+                        s.as_mut()[n.value()] = idx_h(f_hlp, x, n);
+                    }
+                    zou_he::<T, D>(s.as_mut(), normal, prescribed);
+                    r = Some(collision(&s));
+                }
+                Type::ExtrapolatedVelocity | Type::ExtrapolatedPressure(_) => {
+                    // equilibrium of the prescribed values plus the
+                    // non-equilibrium part of the fluid neighbor:
+                    let n_f: D =
+                        self.fluid_neighbor(&**bc, x, &neighbor, &flag);
+                    let x_f = neighbor(x, n_f).unwrap();
+                    let mut f_f = D::Storage::default();
+                    for n in D::all() {
+                        f_f.as_mut()[n.value()] = idx_h(f_hlp, x_f, n);
+                    }
+                    let (rho_f, u_f) = moments::<T, D>(f_f.as_ref());
+                    let (rho, u) = match bc.condition() {
+                        Type::ExtrapolatedPressure(rho) => (t(rho), u_f),
+                        _ => {
+                            let u = bc.velocity(x, n_it);
+                            (rho_f, [t(u[0]), t(u[1]), t(u[2])])
+                        }
+                    };
+                    let f_equ_f = equilibrium(rho_f, u_f);
+                    let mut s = equilibrium(rho, u);
+                    for n in D::all() {
+                        let n = n.value();
+                        s.as_mut()[n] += f_f.as_ref()[n] - f_equ_f.as_ref()[n];
+                    }
+                    r = Some(collision(&s));
+                }
+                Type::ZeroGradient => {
+                    let n_f: D =
+                        self.fluid_neighbor(&**bc, x, &neighbor, &flag);
+                    let x_f = neighbor(x, n_f).unwrap();
+                    let mut s = D::Storage::default();
+                    for n in D::all() {
+                        s.as_mut()[n.value()] = idx_h(f_hlp, x, n);
+                    }
+                    // populations entering the fluid:
+                    let c_f = n_f.direction().array();
+                    for n in D::all() {
+                        let c = n.direction().array();
+                        if c[0] * c_f[0] + c[1] * c_f[1] + c[2] * c_f[2] > 0 {
+                            s.as_mut()[n.value()] = idx_h(f_hlp, x_f, n);
+                        }
+                    }
+                    r = Some(collision(&s));
+                }
+                Type::Convective(u) => {
+                    // explicit upwind step of the post-collision populations
+                    // of the previous iteration, which are the streamed ones
+                    // of the downstream neighbors (the streamed ones of the
+                    // cell itself at the end of a non-periodic axis):
+                    let n_f: D =
+                        self.fluid_neighbor(&**bc, x, &neighbor, &flag);
+                    let x_f = neighbor(x, n_f).unwrap();
+                    let mut s = D::Storage::default();
+                    for n in D::all() {
+                        let f_post = |x: grid::X| {
+                            let x_n = neighbor(x, n).unwrap_or(x);
+                            idx_h(f_hlp, x_n, n)
+                        };
+                        let (f_b, f_f) = (f_post(x), f_post(x_f));
+                        s.as_mut()[n.value()] = f_b - t(u) * (f_b - f_f);
+                    }
+                    r = Some(s);
+                }
             }
         }
         r
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::{D2Q9, D3Q19};
+    use physics::ns;
+
+    /// Non-equilibrium populations.
+    fn populations<D: ns::Distribution>() -> D::Storage<f64> {
+        ns::populations::<D>(1., [0.; 3], 0.03)
+    }
+
+    fn zou_he_moments<D: ns::Distribution>(normals: &[[i8; 3]]) {
+        let u = [0.04, -0.02, 0.01];
+        let u_ = if D::dimension() == 3 {
+            u
+        } else {
+            [u[0], u[1], 0.]
+        };
+        for &normal in normals {
+            let mut f = populations::<D>();
+            zou_he::<f64, D>(f.as_mut(), normal, ZouHe::Velocity(u_));
+            let (_, u) = moments::<f64, D>(f.as_ref());
+            for d in 0..3 {
+                assert!((u[d] - u_[d]).abs() < 1e-14);
+            }
+
+            let mut f = populations::<D>();
+            zou_he::<f64, D>(f.as_mut(), normal, ZouHe::Density(1.1));
+            let (rho, u) = moments::<f64, D>(f.as_ref());
+            assert!((rho - 1.1).abs() < 1e-14);
+            for d in 0..3 {
+                if normal[d] == 0 {
+                    assert!(u[d].abs() < 1e-14);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flags() {
+        use geometry::Plane;
+        let grid = grid::StructuredRectangular::new(4, 3);
+        let mut bcs = Handler::default();
+        bcs.push(Box::new(
+            Condition::new(Type::Velocity, Plane::new((1, 0), (0, 0))),
+        ));
+        bcs.push(Box::new(
+            Condition::new(Type::BounceBack, Plane::new((0, 1), (0, 0))),
+        ));
+        let flags = bcs.flags(&grid);
+        let flag = |x, y| flags.flag(grid.idx(grid::X(x, y, 0)));
+        assert_eq!(flag(0, 0), Flag::Solid(0));
+        assert_eq!(flag(2, 0), Flag::Solid(1));
+        assert_eq!(flag(0, 2), Flag::Boundary(0));
+        assert_eq!(flag(2, 2), Flag::Fluid);
+
+        // the cells of the walls in order, the corner with both conditions:
+        assert_eq!(flags.len(), 6);
+        assert_eq!(flags.cell(0), (grid.idx(grid::X(0, 0, 0)), &[0, 1][..]));
+        assert_eq!(flags.cell(1), (grid.idx(grid::X(1, 0, 0)), &[1][..]));
+        assert_eq!(flags.cell(4), (grid.idx(grid::X(0, 1, 0)), &[0][..]));
+        assert_eq!(flags.conditions(grid.idx(grid::X(0, 0, 0))), &[0, 1]);
+        assert!(flags.conditions(grid.idx(grid::X(2, 2, 0))).is_empty());
+    }
+
+    #[test]
+    fn zou_he_d2q9() {
+        zou_he_moments::<D2Q9>(&[[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0]]);
+    }
+
+    #[test]
+    fn zou_he_d3q19() {
+        zou_he_moments::<D3Q19>(&[[1, 0, 0], [0, -1, 0], [0, 0, 1]]);
+    }
+}
@@ -0,0 +1,136 @@
+use std;
+use Float;
+use geometry::Direction;
+use traits;
+
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[repr(usize)]
+pub enum D1Q3 {
+    C = 0,
+    E = 1,
+    W = 2,
+}
+
+impl D1Q3 {
+    #[inline(always)]
+    fn new(v: usize) -> D1Q3 {
+        assert!(v < 3);
+        unsafe { std::mem::transmute(v) }
+    }
+}
+
+type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> D1Q3>;
+
+impl traits::Distribution for D1Q3 {
+    type Storage<T: Float> = [T; 3];
+    type AllIterator = Iter;
+    #[inline(always)]
+    fn c_squ() -> f64 {
+        1. / 3.
+    }
+    #[inline(always)]
+    fn dimension() -> usize {
+        1
+    }
+    #[inline(always)]
+    fn size() -> usize {
+        3
+    }
+    #[inline(always)]
+    fn all() -> Self::AllIterator {
+        (0..Self::size()).map(D1Q3::new)
+    }
+    #[inline(always)]
+    fn opposite(&self) -> D1Q3 {
+        use self::D1Q3::*;
+        match *self {
+            C => C,
+            E => W,
+            W => E,
+        }
+    }
+    #[inline(always)]
+    fn value(&self) -> usize {
+        *self as usize
+    }
+    #[inline(always)]
+    fn constant(&self) -> f64 {
+        use self::D1Q3::*;
+        match *self {
+            C => 2. / 3.,
+            E | W => 1. / 6.,
+        }
+    }
+    #[inline(always)]
+    fn direction(&self) -> Direction {
+        use self::D1Q3::*;
+        match *self {
+            C => Direction::C,
+            E => Direction::E,
+            W => Direction::W,
+        }
+    }
+
+    #[inline(always)]
+    fn from_direction(d: Direction) -> Option<Self> {
+        use self::D1Q3::*;
+        Some(match d {
+            Direction::C => C,
+            Direction::E => E,
+            Direction::W => W,
+            _ => return None,
+        })
+    }
+    #[inline(always)]
+    fn center() -> Self {
+        D1Q3::C
+    }
+}
+
+impl traits::DirectDistribution for D1Q3 {
+    type DirectIterator = Iter;
+    #[inline(always)]
+    fn direct() -> Self::DirectIterator {
+        (1..3).map(D1Q3::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::D1Q3;
+    use traits::Distribution;
+    #[test]
+    fn opposite() {
+        assert_eq!(D1Q3::C.opposite(), D1Q3::C);
+        assert_eq!(D1Q3::E.opposite(), D1Q3::W);
+        assert_eq!(D1Q3::W.opposite(), D1Q3::E);
+    }
+    #[test]
+    fn velocities() {
+        assert_eq!(D1Q3::C.direction().array(), [0, 0, 0]);
+        assert_eq!(D1Q3::E.direction().array(), [1, 0, 0]);
+        assert_eq!(D1Q3::W.direction().array(), [-1, 0, 0]);
+    }
+    #[test]
+    fn values() {
+        let mut c = 0;
+        for i in D1Q3::all() {
+            let v = i.value();
+            assert_eq!(v, c);
+            let j = D1Q3::new(v);
+            assert_eq!(i, j);
+            c += 1;
+        }
+        assert_eq!(D1Q3::size(), D1Q3::all().count());
+    }
+    #[test]
+    fn weights() {
+        let sum: f64 = D1Q3::all().map(|n| n.constant()).sum();
+        assert!((sum - 1.).abs() < 1e-14);
+        // second moment of the weights must be c_s^2
+        let m: f64 = D1Q3::all()
+            .map(|n| n.constant() * n.direction().num_array()[0].powi(2))
+            .sum();
+        assert!((m - D1Q3::c_squ()).abs() < 1e-14);
+    }
+}
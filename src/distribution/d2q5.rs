@@ -0,0 +1,150 @@
+use std;
+use Float;
+use geometry::Direction;
+use traits;
+
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[repr(usize)]
+pub enum D2Q5 {
+    C = 0,
+    E = 1,
+    N = 2,
+    W = 3,
+    S = 4,
+}
+
+impl D2Q5 {
+    #[inline(always)]
+    fn new(v: usize) -> D2Q5 {
+        assert!(v < 5);
+        unsafe { std::mem::transmute(v) }
+    }
+}
+
+type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> D2Q5>;
+
+impl traits::Distribution for D2Q5 {
+    type Storage<T: Float> = [T; 5];
+    type AllIterator = Iter;
+    #[inline(always)]
+    fn c_squ() -> f64 {
+        1. / 3.
+    }
+    #[inline(always)]
+    fn dimension() -> usize {
+        2
+    }
+    #[inline(always)]
+    fn size() -> usize {
+        5
+    }
+    #[inline(always)]
+    fn all() -> Self::AllIterator {
+        (0..Self::size()).map(D2Q5::new)
+    }
+    #[inline(always)]
+    fn opposite(&self) -> D2Q5 {
+        use self::D2Q5::*;
+        match *self {
+            C => C,
+            E => W,
+            N => S,
+            W => E,
+            S => N,
+        }
+    }
+    #[inline(always)]
+    fn value(&self) -> usize {
+        *self as usize
+    }
+    #[inline(always)]
+    fn constant(&self) -> f64 {
+        use self::D2Q5::*;
+        match *self {
+            C => 1. / 3.,
+            E | N | W | S => 1. / 6.,
+        }
+    }
+    #[inline(always)]
+    fn direction(&self) -> Direction {
+        use self::D2Q5::*;
+        match *self {
+            C => Direction::C,
+            E => Direction::E,
+            N => Direction::N,
+            W => Direction::W,
+            S => Direction::S,
+        }
+    }
+
+    #[inline(always)]
+    fn from_direction(d: Direction) -> Option<Self> {
+        use self::D2Q5::*;
+        Some(match d {
+            Direction::C => C,
+            Direction::E => E,
+            Direction::N => N,
+            Direction::W => W,
+            Direction::S => S,
+            _ => return None,
+        })
+    }
+    #[inline(always)]
+    fn center() -> Self {
+        D2Q5::C
+    }
+}
+
+impl traits::DirectDistribution for D2Q5 {
+    type DirectIterator = Iter;
+    #[inline(always)]
+    fn direct() -> Self::DirectIterator {
+        (1..5).map(D2Q5::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::D2Q5;
+    use traits::Distribution;
+    #[test]
+    fn opposite() {
+        assert_eq!(D2Q5::C.opposite(), D2Q5::C);
+        assert_eq!(D2Q5::E.opposite(), D2Q5::W);
+        assert_eq!(D2Q5::N.opposite(), D2Q5::S);
+        assert_eq!(D2Q5::W.opposite(), D2Q5::E);
+        assert_eq!(D2Q5::S.opposite(), D2Q5::N);
+    }
+    #[test]
+    fn velocities() {
+        assert_eq!(D2Q5::C.direction().array(), [0, 0, 0]);
+        assert_eq!(D2Q5::E.direction().array(), [1, 0, 0]);
+        assert_eq!(D2Q5::N.direction().array(), [0, 1, 0]);
+        assert_eq!(D2Q5::W.direction().array(), [-1, 0, 0]);
+        assert_eq!(D2Q5::S.direction().array(), [0, -1, 0]);
+    }
+    #[test]
+    fn values() {
+        let mut c = 0;
+        for i in D2Q5::all() {
+            let v = i.value();
+            assert_eq!(v, c);
+            let j = D2Q5::new(v);
+            assert_eq!(i, j);
+            c += 1;
+        }
+        assert_eq!(D2Q5::size(), D2Q5::all().count());
+    }
+    #[test]
+    fn weights() {
+        let sum: f64 = D2Q5::all().map(|n| n.constant()).sum();
+        assert!((sum - 1.).abs() < 1e-14);
+        // second moment of the weights must be isotropic: c_s^2 I
+        for d in 0..2 {
+            let m: f64 = D2Q5::all()
+                .map(|n| n.constant() * n.direction().num_array()[d].powi(2))
+                .sum();
+            assert!((m - D2Q5::c_squ()).abs() < 1e-14);
+        }
+    }
+}
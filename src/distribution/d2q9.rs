@@ -1,5 +1,5 @@
 use std;
-use num;
+use Float;
 use geometry::Direction;
 use traits;
 
@@ -28,13 +28,17 @@ impl D2Q9 {
 type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> D2Q9>;
 
 impl traits::Distribution for D2Q9 {
-    type Storage = [num; 9];
+    type Storage<T: Float> = [T; 9];
     type AllIterator = Iter;
     #[inline(always)]
-    fn c_squ() -> num {
+    fn c_squ() -> f64 {
         1. / 3.
     }
     #[inline(always)]
+    fn dimension() -> usize {
+        2
+    }
+    #[inline(always)]
     fn size() -> usize {
         9
     }
@@ -62,7 +66,7 @@ impl traits::Distribution for D2Q9 {
         *self as usize
     }
     #[inline(always)]
-    fn constant(&self) -> num {
+    fn constant(&self) -> f64 {
         use self::D2Q9::*;
         match *self {
             C => 4. / 9.,
@@ -99,6 +103,7 @@ impl traits::Distribution for D2Q9 {
             Direction::NW => NW,
             Direction::SW => SW,
             Direction::SE => SE,
+            _ => return None,
         })
     }
     #[inline(always)]
@@ -126,6 +131,7 @@ impl traits::DiagonalDistribution for D2Q9 {
 #[cfg(test)]
 mod tests {
     use super::D2Q9;
+    use traits::Distribution;
     #[test]
     fn opposite() {
         assert_eq!(D2Q9::C.opposite(), D2Q9::C);
@@ -140,15 +146,15 @@ mod tests {
     }
     #[test]
     fn velocities() {
-        assert_eq!(D2Q9::C.direction(), [0, 0]);
-        assert_eq!(D2Q9::E.direction(), [1, 0]);
-        assert_eq!(D2Q9::N.direction(), [0, 1]);
-        assert_eq!(D2Q9::W.direction(), [-1, 0]);
-        assert_eq!(D2Q9::S.direction(), [0, -1]);
-        assert_eq!(D2Q9::NE.direction(), [1, 1]);
-        assert_eq!(D2Q9::NW.direction(), [-1, 1]);
-        assert_eq!(D2Q9::SW.direction(), [-1, -1]);
-        assert_eq!(D2Q9::SE.direction(), [1, -1]);
+        assert_eq!(D2Q9::C.direction().array(), [0, 0, 0]);
+        assert_eq!(D2Q9::E.direction().array(), [1, 0, 0]);
+        assert_eq!(D2Q9::N.direction().array(), [0, 1, 0]);
+        assert_eq!(D2Q9::W.direction().array(), [-1, 0, 0]);
+        assert_eq!(D2Q9::S.direction().array(), [0, -1, 0]);
+        assert_eq!(D2Q9::NE.direction().array(), [1, 1, 0]);
+        assert_eq!(D2Q9::NW.direction().array(), [-1, 1, 0]);
+        assert_eq!(D2Q9::SW.direction().array(), [-1, -1, 0]);
+        assert_eq!(D2Q9::SE.direction().array(), [1, -1, 0]);
     }
     #[test]
     fn values() {
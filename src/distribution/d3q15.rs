@@ -0,0 +1,205 @@
+use std;
+use Float;
+use geometry::Direction;
+use traits;
+
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[repr(usize)]
+pub enum D3Q15 {
+    C = 0,
+    E = 1,
+    N = 2,
+    W = 3,
+    S = 4,
+    T = 5,
+    B = 6,
+    NET = 7,
+    NWT = 8,
+    SWT = 9,
+    SET = 10,
+    NEB = 11,
+    NWB = 12,
+    SWB = 13,
+    SEB = 14,
+}
+
+impl D3Q15 {
+    #[inline(always)]
+    fn new(v: usize) -> D3Q15 {
+        assert!(v < 15);
+        unsafe { std::mem::transmute(v) }
+    }
+}
+
+type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> D3Q15>;
+
+impl traits::Distribution for D3Q15 {
+    type Storage<T: Float> = [T; 15];
+    type AllIterator = Iter;
+    #[inline(always)]
+    fn c_squ() -> f64 {
+        1. / 3.
+    }
+    #[inline(always)]
+    fn dimension() -> usize {
+        3
+    }
+    #[inline(always)]
+    fn size() -> usize {
+        15
+    }
+    #[inline(always)]
+    fn all() -> Self::AllIterator {
+        (0..Self::size()).map(D3Q15::new)
+    }
+    #[inline(always)]
+    fn opposite(&self) -> D3Q15 {
+        use self::D3Q15::*;
+        match *self {
+            C => C,
+            E => W,
+            N => S,
+            W => E,
+            S => N,
+            T => B,
+            B => T,
+            NET => SWB,
+            NWT => SEB,
+            SWT => NEB,
+            SET => NWB,
+            NEB => SWT,
+            NWB => SET,
+            SWB => NET,
+            SEB => NWT,
+        }
+    }
+    #[inline(always)]
+    fn value(&self) -> usize {
+        *self as usize
+    }
+    #[inline(always)]
+    fn constant(&self) -> f64 {
+        use self::D3Q15::*;
+        match *self {
+            C => 2. / 9.,
+            E | N | W | S | T | B => 1. / 9.,
+            _ => 1. / 72.,
+        }
+    }
+    #[inline(always)]
+    fn direction(&self) -> Direction {
+        use self::D3Q15::*;
+        match *self {
+            C => Direction::C,
+            E => Direction::E,
+            N => Direction::N,
+            W => Direction::W,
+            S => Direction::S,
+            T => Direction::T,
+            B => Direction::B,
+            NET => Direction::NET,
+            NWT => Direction::NWT,
+            SWT => Direction::SWT,
+            SET => Direction::SET,
+            NEB => Direction::NEB,
+            NWB => Direction::NWB,
+            SWB => Direction::SWB,
+            SEB => Direction::SEB,
+        }
+    }
+
+    #[inline(always)]
+    fn from_direction(d: Direction) -> Option<Self> {
+        use self::D3Q15::*;
+        Some(match d {
+            Direction::C => C,
+            Direction::E => E,
+            Direction::N => N,
+            Direction::W => W,
+            Direction::S => S,
+            Direction::T => T,
+            Direction::B => B,
+            Direction::NET => NET,
+            Direction::NWT => NWT,
+            Direction::SWT => SWT,
+            Direction::SET => SET,
+            Direction::NEB => NEB,
+            Direction::NWB => NWB,
+            Direction::SWB => SWB,
+            Direction::SEB => SEB,
+            _ => return None,
+        })
+    }
+    #[inline(always)]
+    fn center() -> Self {
+        D3Q15::C
+    }
+}
+
+impl traits::DirectDistribution for D3Q15 {
+    type DirectIterator = Iter;
+    #[inline(always)]
+    fn direct() -> Self::DirectIterator {
+        (1..7).map(D3Q15::new)
+    }
+}
+
+impl traits::DiagonalDistribution for D3Q15 {
+    type DiagonalIterator = Iter;
+    #[inline(always)]
+    fn diagonal() -> Self::DiagonalIterator {
+        (7..15).map(D3Q15::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::D3Q15;
+    use traits::Distribution;
+    #[test]
+    fn opposite() {
+        for n in D3Q15::all() {
+            let v = n.direction().array();
+            let o = n.opposite().direction().array();
+            assert_eq!([-v[0], -v[1], -v[2]], o);
+            assert_eq!(n.opposite().opposite(), n);
+        }
+    }
+    #[test]
+    fn velocities() {
+        assert_eq!(D3Q15::C.direction().array(), [0, 0, 0]);
+        assert_eq!(D3Q15::E.direction().array(), [1, 0, 0]);
+        assert_eq!(D3Q15::B.direction().array(), [0, 0, -1]);
+        assert_eq!(D3Q15::NET.direction().array(), [1, 1, 1]);
+        assert_eq!(D3Q15::SWB.direction().array(), [-1, -1, -1]);
+        assert_eq!(D3Q15::SET.direction().array(), [1, -1, 1]);
+        for n in D3Q15::all() {
+            let m = D3Q15::from_direction(n.direction()).unwrap();
+            assert_eq!(m, n);
+        }
+    }
+    #[test]
+    fn values() {
+        let mut c = 0;
+        for i in D3Q15::all() {
+            let v = i.value();
+            assert_eq!(v, c);
+            let j = D3Q15::new(v);
+            assert_eq!(i, j);
+            c += 1;
+        }
+        assert_eq!(D3Q15::size(), D3Q15::all().count());
+    }
+    #[test]
+    fn weights() {
+        let sum: f64 = D3Q15::all().map(|n| n.constant()).sum();
+        assert!((sum - 1.).abs() < 1e-14);
+        // second moment of the weights must be isotropic: c_s^2 I
+        for d in 0..3 {
+            let m: f64 = D3Q15::all()
+                .map(|n| n.constant() * n.direction().num_array()[d].powi(2))
+                .sum();
+            assert!((m - D3Q15::c_squ()).abs() < 1e-14);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std;
+use Float;
+use geometry::Direction;
+use traits;
+
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[repr(usize)]
+pub enum D3Q19 {
+    C = 0,
+    E = 1,
+    N = 2,
+    W = 3,
+    S = 4,
+    T = 5,
+    B = 6,
+    NE = 7,
+    NW = 8,
+    SW = 9,
+    SE = 10,
+    ET = 11,
+    WT = 12,
+    NT = 13,
+    ST = 14,
+    EB = 15,
+    WB = 16,
+    NB = 17,
+    SB = 18,
+}
+
+impl D3Q19 {
+    #[inline(always)]
+    fn new(v: usize) -> D3Q19 {
+        assert!(v < 19);
+        unsafe { std::mem::transmute(v) }
+    }
+}
+
+type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> D3Q19>;
+
+impl traits::Distribution for D3Q19 {
+    type Storage<T: Float> = [T; 19];
+    type AllIterator = Iter;
+    #[inline(always)]
+    fn c_squ() -> f64 {
+        1. / 3.
+    }
+    #[inline(always)]
+    fn dimension() -> usize {
+        3
+    }
+    #[inline(always)]
+    fn size() -> usize {
+        19
+    }
+    #[inline(always)]
+    fn all() -> Self::AllIterator {
+        (0..Self::size()).map(D3Q19::new)
+    }
+    #[inline(always)]
+    fn opposite(&self) -> D3Q19 {
+        use self::D3Q19::*;
+        match *self {
+            C => C,
+            E => W,
+            N => S,
+            W => E,
+            S => N,
+            T => B,
+            B => T,
+            NE => SW,
+            NW => SE,
+            SW => NE,
+            SE => NW,
+            ET => WB,
+            WT => EB,
+            NT => SB,
+            ST => NB,
+            EB => WT,
+            WB => ET,
+            NB => ST,
+            SB => NT,
+        }
+    }
+    #[inline(always)]
+    fn value(&self) -> usize {
+        *self as usize
+    }
+    #[inline(always)]
+    fn constant(&self) -> f64 {
+        use self::D3Q19::*;
+        match *self {
+            C => 1. / 3.,
+            E | N | W | S | T | B => 1. / 18.,
+            _ => 1. / 36.,
+        }
+    }
+    #[inline(always)]
+    fn direction(&self) -> Direction {
+        use self::D3Q19::*;
+        match *self {
+            C => Direction::C,
+            E => Direction::E,
+            N => Direction::N,
+            W => Direction::W,
+            S => Direction::S,
+            T => Direction::T,
+            B => Direction::B,
+            NE => Direction::NE,
+            NW => Direction::NW,
+            SW => Direction::SW,
+            SE => Direction::SE,
+            ET => Direction::ET,
+            WT => Direction::WT,
+            NT => Direction::NT,
+            ST => Direction::ST,
+            EB => Direction::EB,
+            WB => Direction::WB,
+            NB => Direction::NB,
+            SB => Direction::SB,
+        }
+    }
+
+    #[inline(always)]
+    fn from_direction(d: Direction) -> Option<Self> {
+        use self::D3Q19::*;
+        Some(match d {
+            Direction::C => C,
+            Direction::E => E,
+            Direction::N => N,
+            Direction::W => W,
+            Direction::S => S,
+            Direction::T => T,
+            Direction::B => B,
+            Direction::NE => NE,
+            Direction::NW => NW,
+            Direction::SW => SW,
+            Direction::SE => SE,
+            Direction::ET => ET,
+            Direction::WT => WT,
+            Direction::NT => NT,
+            Direction::ST => ST,
+            Direction::EB => EB,
+            Direction::WB => WB,
+            Direction::NB => NB,
+            Direction::SB => SB,
+            _ => return None,
+        })
+    }
+    #[inline(always)]
+    fn center() -> Self {
+        D3Q19::C
+    }
+}
+
+impl traits::DirectDistribution for D3Q19 {
+    type DirectIterator = Iter;
+    #[inline(always)]
+    fn direct() -> Self::DirectIterator {
+        (1..7).map(D3Q19::new)
+    }
+}
+
+impl traits::DiagonalDistribution for D3Q19 {
+    type DiagonalIterator = Iter;
+    #[inline(always)]
+    fn diagonal() -> Self::DiagonalIterator {
+        (7..19).map(D3Q19::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::D3Q19;
+    use traits::Distribution;
+    #[test]
+    fn opposite() {
+        for n in D3Q19::all() {
+            let v = n.direction().array();
+            let o = n.opposite().direction().array();
+            assert_eq!([-v[0], -v[1], -v[2]], o);
+            assert_eq!(n.opposite().opposite(), n);
+        }
+    }
+    #[test]
+    fn velocities() {
+        assert_eq!(D3Q19::C.direction().array(), [0, 0, 0]);
+        assert_eq!(D3Q19::E.direction().array(), [1, 0, 0]);
+        assert_eq!(D3Q19::T.direction().array(), [0, 0, 1]);
+        assert_eq!(D3Q19::B.direction().array(), [0, 0, -1]);
+        assert_eq!(D3Q19::NE.direction().array(), [1, 1, 0]);
+        assert_eq!(D3Q19::WT.direction().array(), [-1, 0, 1]);
+        assert_eq!(D3Q19::SB.direction().array(), [0, -1, -1]);
+        for n in D3Q19::all() {
+            let m = D3Q19::from_direction(n.direction()).unwrap();
+            assert_eq!(m, n);
+        }
+    }
+    #[test]
+    fn values() {
+        let mut c = 0;
+        for i in D3Q19::all() {
+            let v = i.value();
+            assert_eq!(v, c);
+            let j = D3Q19::new(v);
+            assert_eq!(i, j);
+            c += 1;
+        }
+        assert_eq!(D3Q19::size(), D3Q19::all().count());
+    }
+    #[test]
+    fn weights() {
+        let sum: f64 = D3Q19::all().map(|n| n.constant()).sum();
+        assert!((sum - 1.).abs() < 1e-14);
+        // second moment of the weights must be isotropic: c_s^2 I
+        for d in 0..3 {
+            let m: f64 = D3Q19::all()
+                .map(|n| n.constant() * n.direction().num_array()[d].powi(2))
+                .sum();
+            assert!((m - D3Q19::c_squ()).abs() < 1e-14);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std;
+use Float;
+use geometry::Direction;
+use traits;
+
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+#[repr(usize)]
+pub enum D3Q27 {
+    C = 0,
+    E = 1,
+    N = 2,
+    W = 3,
+    S = 4,
+    T = 5,
+    B = 6,
+    NE = 7,
+    NW = 8,
+    SW = 9,
+    SE = 10,
+    ET = 11,
+    WT = 12,
+    NT = 13,
+    ST = 14,
+    EB = 15,
+    WB = 16,
+    NB = 17,
+    SB = 18,
+    NET = 19,
+    NWT = 20,
+    SWT = 21,
+    SET = 22,
+    NEB = 23,
+    NWB = 24,
+    SWB = 25,
+    SEB = 26,
+}
+
+impl D3Q27 {
+    #[inline(always)]
+    fn new(v: usize) -> D3Q27 {
+        assert!(v < 27);
+        unsafe { std::mem::transmute(v) }
+    }
+}
+
+type Iter = std::iter::Map<std::ops::Range<usize>, fn(usize) -> D3Q27>;
+
+impl traits::Distribution for D3Q27 {
+    type Storage<T: Float> = [T; 27];
+    type AllIterator = Iter;
+    #[inline(always)]
+    fn c_squ() -> f64 {
+        1. / 3.
+    }
+    #[inline(always)]
+    fn dimension() -> usize {
+        3
+    }
+    #[inline(always)]
+    fn size() -> usize {
+        27
+    }
+    #[inline(always)]
+    fn all() -> Self::AllIterator {
+        (0..Self::size()).map(D3Q27::new)
+    }
+    #[inline(always)]
+    fn opposite(&self) -> D3Q27 {
+        use self::D3Q27::*;
+        match *self {
+            C => C,
+            E => W,
+            N => S,
+            W => E,
+            S => N,
+            T => B,
+            B => T,
+            NE => SW,
+            NW => SE,
+            SW => NE,
+            SE => NW,
+            ET => WB,
+            WT => EB,
+            NT => SB,
+            ST => NB,
+            EB => WT,
+            WB => ET,
+            NB => ST,
+            SB => NT,
+            NET => SWB,
+            NWT => SEB,
+            SWT => NEB,
+            SET => NWB,
+            NEB => SWT,
+            NWB => SET,
+            SWB => NET,
+            SEB => NWT,
+        }
+    }
+    #[inline(always)]
+    fn value(&self) -> usize {
+        *self as usize
+    }
+    #[inline(always)]
+    fn constant(&self) -> f64 {
+        use self::D3Q27::*;
+        match *self {
+            C => 8. / 27.,
+            E | N | W | S | T | B => 2. / 27.,
+            NET | NWT | SWT | SET | NEB | NWB | SWB | SEB => 1. / 216.,
+            _ => 1. / 54.,
+        }
+    }
+    #[inline(always)]
+    fn direction(&self) -> Direction {
+        use self::D3Q27::*;
+        match *self {
+            C => Direction::C,
+            E => Direction::E,
+            N => Direction::N,
+            W => Direction::W,
+            S => Direction::S,
+            T => Direction::T,
+            B => Direction::B,
+            NE => Direction::NE,
+            NW => Direction::NW,
+            SW => Direction::SW,
+            SE => Direction::SE,
+            ET => Direction::ET,
+            WT => Direction::WT,
+            NT => Direction::NT,
+            ST => Direction::ST,
+            EB => Direction::EB,
+            WB => Direction::WB,
+            NB => Direction::NB,
+            SB => Direction::SB,
+            NET => Direction::NET,
+            NWT => Direction::NWT,
+            SWT => Direction::SWT,
+            SET => Direction::SET,
+            NEB => Direction::NEB,
+            NWB => Direction::NWB,
+            SWB => Direction::SWB,
+            SEB => Direction::SEB,
+        }
+    }
+
+    #[inline(always)]
+    fn from_direction(d: Direction) -> Option<Self> {
+        use self::D3Q27::*;
+        Some(match d {
+            Direction::C => C,
+            Direction::E => E,
+            Direction::N => N,
+            Direction::W => W,
+            Direction::S => S,
+            Direction::T => T,
+            Direction::B => B,
+            Direction::NE => NE,
+            Direction::NW => NW,
+            Direction::SW => SW,
+            Direction::SE => SE,
+            Direction::ET => ET,
+            Direction::WT => WT,
+            Direction::NT => NT,
+            Direction::ST => ST,
+            Direction::EB => EB,
+            Direction::WB => WB,
+            Direction::NB => NB,
+            Direction::SB => SB,
+            Direction::NET => NET,
+            Direction::NWT => NWT,
+            Direction::SWT => SWT,
+            Direction::SET => SET,
+            Direction::NEB => NEB,
+            Direction::NWB => NWB,
+            Direction::SWB => SWB,
+            Direction::SEB => SEB,
+        })
+    }
+    #[inline(always)]
+    fn center() -> Self {
+        D3Q27::C
+    }
+}
+
+impl traits::DirectDistribution for D3Q27 {
+    type DirectIterator = Iter;
+    #[inline(always)]
+    fn direct() -> Self::DirectIterator {
+        (1..7).map(D3Q27::new)
+    }
+}
+
+impl traits::DiagonalDistribution for D3Q27 {
+    type DiagonalIterator = Iter;
+    #[inline(always)]
+    fn diagonal() -> Self::DiagonalIterator {
+        (7..27).map(D3Q27::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::D3Q27;
+    use traits::Distribution;
+    #[test]
+    fn opposite() {
+        for n in D3Q27::all() {
+            let v = n.direction().array();
+            let o = n.opposite().direction().array();
+            assert_eq!([-v[0], -v[1], -v[2]], o);
+            assert_eq!(n.opposite().opposite(), n);
+        }
+    }
+    #[test]
+    fn velocities() {
+        assert_eq!(D3Q27::C.direction().array(), [0, 0, 0]);
+        assert_eq!(D3Q27::E.direction().array(), [1, 0, 0]);
+        assert_eq!(D3Q27::T.direction().array(), [0, 0, 1]);
+        assert_eq!(D3Q27::NE.direction().array(), [1, 1, 0]);
+        assert_eq!(D3Q27::WB.direction().array(), [-1, 0, -1]);
+        assert_eq!(D3Q27::NET.direction().array(), [1, 1, 1]);
+        assert_eq!(D3Q27::SWB.direction().array(), [-1, -1, -1]);
+        for n in D3Q27::all() {
+            let m = D3Q27::from_direction(n.direction()).unwrap();
+            assert_eq!(m, n);
+        }
+    }
+    #[test]
+    fn values() {
+        let mut c = 0;
+        for i in D3Q27::all() {
+            let v = i.value();
+            assert_eq!(v, c);
+            let j = D3Q27::new(v);
+            assert_eq!(i, j);
+            c += 1;
+        }
+        assert_eq!(D3Q27::size(), D3Q27::all().count());
+    }
+    #[test]
+    fn weights() {
+        let sum: f64 = D3Q27::all().map(|n| n.constant()).sum();
+        assert!((sum - 1.).abs() < 1e-14);
+        // second moment of the weights must be isotropic: c_s^2 I
+        for d in 0..3 {
+            let m: f64 = D3Q27::all()
+                .map(|n| n.constant() * n.direction().num_array()[d].powi(2))
+                .sum();
+            assert!((m - D3Q27::c_squ()).abs() < 1e-14);
+        }
+    }
+}
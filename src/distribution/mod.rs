@@ -1,2 +1,12 @@
+mod d1q3;
+mod d2q5;
 mod d2q9;
+mod d3q15;
+mod d3q19;
+mod d3q27;
+pub use self::d1q3::D1Q3;
+pub use self::d2q5::D2Q5;
 pub use self::d2q9::D2Q9;
+pub use self::d3q15::D3Q15;
+pub use self::d3q19::D3Q19;
+pub use self::d3q27::D3Q27;
@@ -0,0 +1,141 @@
+//! Floating-point types of the populations.
+
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub,
+               SubAssign};
+use std::simd::{Simd, SimdElement};
+use io::vtk;
+
+/// Floating-point type of the populations and of the computations on them.
+///
+/// Constants are written as `f64` literals and converted with `from_f64`.
+pub trait Float
+    : Copy
+    + Default
+    + Debug
+    + Display
+    + PartialEq
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Sum
+    + vtk::Primitive
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign {
+    /// Vectors of 4 values
+    type X4: Vector<Self>;
+    /// Vectors of 8 values
+    type X8: Vector<Self>;
+
+    /// Nearest value to `v`
+    fn from_f64(v: f64) -> Self;
+    /// Value as `f64`
+    fn to_f64(self) -> f64;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn powf(self, n: Self) -> Self;
+}
+
+/// Portable SIMD vector of `T`s.
+pub trait Vector<T>
+    : Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + AddAssign
+    + DivAssign {
+    /// Number of values of the vector
+    const LANES: usize;
+    /// Vector with all values `v`
+    fn splat(v: T) -> Self;
+    /// Vector of the first `LANES` values of `s`
+    fn from_slice(s: &[T]) -> Self;
+    /// Writes the values to the first `LANES` values of `s`
+    fn copy_to_slice(self, s: &mut [T]);
+}
+
+impl<T, const N: usize> Vector<T> for Simd<T, N>
+where
+    T: SimdElement,
+    Simd<T, N>: Copy
+        + Add<Output = Self>
+        + Sub<Output = Self>
+        + Mul<Output = Self>
+        + Div<Output = Self>
+        + AddAssign
+        + DivAssign,
+{
+    const LANES: usize = N;
+    #[inline(always)]
+    fn splat(v: T) -> Self {
+        Simd::splat(v)
+    }
+    #[inline(always)]
+    fn from_slice(s: &[T]) -> Self {
+        Simd::from_slice(s)
+    }
+    #[inline(always)]
+    fn copy_to_slice(self, s: &mut [T]) {
+        Simd::copy_to_slice(self, s)
+    }
+}
+
+impl Float for f32 {
+    type X4 = Simd<f32, 4>;
+    type X8 = Simd<f32, 8>;
+    #[inline(always)]
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+    #[inline(always)]
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+    #[inline(always)]
+    fn powf(self, n: Self) -> Self {
+        f32::powf(self, n)
+    }
+}
+
+impl Float for f64 {
+    type X4 = Simd<f64, 4>;
+    type X8 = Simd<f64, 8>;
+    #[inline(always)]
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+    #[inline(always)]
+    fn to_f64(self) -> f64 {
+        self
+    }
+    #[inline(always)]
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+    #[inline(always)]
+    fn powf(self, n: Self) -> Self {
+        f64::powf(self, n)
+    }
+}
@@ -0,0 +1,171 @@
+//! Momentum-exchange evaluation of the loads on the boundary conditions.
+//!
+//! Every link between a solid boundary cell and a fluid cell transfers the
+//! momentum of the population arriving at the wall and of the one leaving
+//! it, `-c_i (f_-i + f_i)`, to the boundary. The loads of a boundary
+//! condition are the sums of these transfers over all of its links.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Cross product `a x b`.
+#[inline(always)]
+pub fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Reference quantities of the force and torque coefficients.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub density: f64,
+    pub velocity: f64,
+    pub length: f64,
+}
+
+impl Reference {
+    pub fn new(density: f64, velocity: f64, length: f64) -> Self {
+        Self {
+            density,
+            velocity,
+            length,
+        }
+    }
+    /// Dynamic pressure `rho U^2 / 2`
+    #[inline(always)]
+    pub fn dynamic_pressure(&self) -> f64 {
+        0.5 * self.density * self.velocity * self.velocity
+    }
+}
+
+/// Force and torque exerted by the fluid on a boundary condition.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Load {
+    pub force: [f64; 3],
+    /// Torque about the point of the `Monitor`
+    pub torque: [f64; 3],
+}
+
+impl Load {
+    /// Adds the momentum `df` transferred at the distance `r` from the
+    /// torque point to the load.
+    #[inline(always)]
+    pub fn add(&mut self, df: [f64; 3], r: [f64; 3]) {
+        let dt = cross(r, df);
+        for d in 0..3 {
+            self.force[d] += df[d];
+            self.torque[d] += dt[d];
+        }
+    }
+    /// Sum of the loads `self` and `o`
+    #[inline(always)]
+    pub fn sum(mut self, o: Load) -> Load {
+        for d in 0..3 {
+            self.force[d] += o.force[d];
+            self.torque[d] += o.torque[d];
+        }
+        self
+    }
+    /// Force coefficients `F / (rho U^2 L^(dim - 1) / 2)` (drag and lift
+    /// for a flow along x) of a `dim`-dimensional flow.
+    pub fn force_coefficients(&self, r: &Reference, dim: usize) -> [f64; 3] {
+        let s = r.dynamic_pressure() * r.length.powi(dim as i32 - 1);
+        [self.force[0] / s, self.force[1] / s, self.force[2] / s]
+    }
+    /// Torque coefficients `T / (rho U^2 L^dim / 2)` of a `dim`-dimensional
+    /// flow.
+    pub fn torque_coefficients(&self, r: &Reference, dim: usize) -> [f64; 3] {
+        let s = r.dynamic_pressure() * r.length.powi(dim as i32);
+        [self.torque[0] / s, self.torque[1] / s, self.torque[2] / s]
+    }
+}
+
+/// Evaluates the loads on the solid boundary conditions at each iteration
+/// and, optionally, logs them to a CSV time series.
+pub struct Monitor {
+    /// Point about which the torques are evaluated
+    pub point: [f64; 3],
+    pub reference: Reference,
+    loads: Vec<Load>,
+    csv: Option<BufWriter<File>>,
+}
+
+impl Monitor {
+    pub fn new(point: [f64; 3], reference: Reference) -> Self {
+        Self {
+            point,
+            reference,
+            loads: Vec::new(),
+            csv: None,
+        }
+    }
+
+    /// Monitor logging the loads and their coefficients of all boundary
+    /// conditions to the CSV file `fname`.csv after every iteration.
+    ///
+    /// Panics if the file cannot be created or written.
+    pub fn with_csv(self, fname: &str) -> Self {
+        let mut csv = BufWriter::new(
+            File::create(format!("{}.csv", fname))
+                .expect("failed to create the loads CSV file"),
+        );
+        csv.write_all(
+            b"iteration,condition,f_x,f_y,f_z,t_x,t_y,t_z,\
+              c_f_x,c_f_y,c_f_z,c_t_x,c_t_y,c_t_z\n",
+        ).expect("failed to write the loads CSV file");
+        Self {
+            csv: Some(csv),
+            ..self
+        }
+    }
+
+    /// Loads of the last evaluated iteration indexed by the boundary
+    /// condition (zero for conditions without solid cells)
+    pub fn loads(&self) -> &[Load] {
+        &self.loads
+    }
+
+    /// Records the loads of the iteration `n_it` of a `dim`-dimensional
+    /// flow.
+    pub fn record(&mut self, n_it: usize, loads: Vec<Load>, dim: usize) {
+        self.loads = loads;
+        if let Some(ref mut csv) = self.csv {
+            for (k, l) in self.loads.iter().enumerate() {
+                let c_f = l.force_coefficients(&self.reference, dim);
+                let c_t = l.torque_coefficients(&self.reference, dim);
+                let mut line = format!("{},{}", n_it, k);
+                let values =
+                    l.force.iter().chain(&l.torque).chain(&c_f).chain(&c_t);
+                for v in values {
+                    line.push_str(&format!(",{}", v));
+                }
+                line.push('\n');
+                csv.write_all(line.as_bytes())
+                    .expect("failed to write the loads CSV file");
+            }
+            // keep the file complete if the run is interrupted:
+            csv.flush().expect("failed to write the loads CSV file");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coefficients() {
+        let mut l = Load::default();
+        l.add([0.2, -0.1, 0.], [0., 2., 0.]);
+        l = l.sum(l);
+        assert_eq!(l.force, [0.4, -0.2, 0.]);
+        assert_eq!(l.torque, [0., 0., -0.8]);
+
+        let r = Reference::new(2., 0.5, 4.);
+        assert_eq!(l.force_coefficients(&r, 2), [0.4, -0.2, 0.]);
+        assert_eq!(l.force_coefficients(&r, 3), [0.1, -0.05, 0.]);
+        assert_eq!(l.torque_coefficients(&r, 2), [0., 0., -0.2]);
+    }
+}
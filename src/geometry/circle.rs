@@ -1,29 +1,69 @@
-use num;
 use grid;
 use super::Geometry;
 
 pub struct Circle {
-    x_c: num,
-    y_c: num,
-    r: num,
+    x_c: f64,
+    y_c: f64,
+    r: f64,
 }
 
 impl Circle {
     pub fn new(lx: usize, ly: usize) -> Self {
-        let lx = lx as num;
-        let ly = ly as num;
+        let lx = lx as f64;
+        let ly = ly as f64;
         Self {
             x_c: lx / 2. - 0.2 * lx,
             y_c: ly / 2.,
             r: 0.125 * ly,
         }
     }
+    /// Coordinates of the center
+    pub fn center(&self) -> [f64; 2] {
+        [self.x_c, self.y_c]
+    }
+    pub fn radius(&self) -> f64 {
+        self.r
+    }
 }
 
 impl Geometry for Circle {
     #[inline(always)]
     fn contains(&self, x: grid::X) -> bool {
-        ((self.x_c - x.0 as num).powf(2.) + (self.y_c - x.1 as num).powf(2.))
+        ((self.x_c - x.0 as f64).powf(2.) + (self.y_c - x.1 as f64).powf(2.))
             .sqrt() - self.r < 0.
     }
+    #[inline(always)]
+    fn intersection(&self, x: grid::X, c: [i8; 3]) -> Option<f64> {
+        // |x + q c - x_c|^2 = r^2
+        let (d_x, d_y) = (x.0 as f64 - self.x_c, x.1 as f64 - self.y_c);
+        let (c_x, c_y) = (f64::from(c[0]), f64::from(c[1]));
+        let a = c_x * c_x + c_y * c_y;
+        let b = 2. * (c_x * d_x + c_y * d_y);
+        let c = d_x * d_x + d_y * d_y - self.r * self.r;
+        let discriminant = b * b - 4. * a * c;
+        if a == 0. || c < 0. || discriminant < 0. {
+            return None;
+        }
+        let q = (-b - discriminant.sqrt()) / (2. * a);
+        if q >= 0. && q <= 1. { Some(q) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersection() {
+        // x_c = 12, y_c = 20, r = 5
+        let c = Circle::new(40, 40);
+        assert_eq!(c.intersection(grid::X(19, 20, 0), [-1, 0, 0]), None);
+        assert_eq!(c.intersection(grid::X(17, 20, 0), [-1, 0, 0]), Some(0.));
+        let q = c.intersection(grid::X(12, 26, 0), [0, -1, 0]).unwrap();
+        assert!((q - 1.).abs() < 1e-14);
+        let q = c.intersection(grid::X(16, 24, 0), [-1, -1, 0]).unwrap();
+        let (x, y) = (16. - q - 12., 24. - q - 20.);
+        assert!((x * x + y * y - 25.).abs() < 1e-12);
+        assert_eq!(c.intersection(grid::X(12, 20, 0), [1, 0, 0]), None);
+    }
 }
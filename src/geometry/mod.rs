@@ -1,4 +1,3 @@
-use num;
 use grid;
 
 mod point;
@@ -11,6 +10,10 @@ pub use self::circle::Circle;
 pub use self::plane::Plane;
 pub use self::rectangle::Rectangle;
 
+/// Lattice directions.
+///
+/// The in-plane directions use compass names (`E`/`W` along x, `N`/`S`
+/// along y); `T`/`B` (top/bottom) point along +z/-z.
 #[repr(usize)]
 pub enum Direction {
     C,
@@ -22,32 +25,82 @@ pub enum Direction {
     NW,
     SW,
     SE,
+    T,
+    B,
+    ET,
+    WT,
+    NT,
+    ST,
+    EB,
+    WB,
+    NB,
+    SB,
+    NET,
+    NWT,
+    SWT,
+    SET,
+    NEB,
+    NWB,
+    SWB,
+    SEB,
 }
 
 impl Direction {
     #[inline(always)]
-    pub fn array(&self) -> [i8; 2] {
+    pub fn array(&self) -> [i8; 3] {
         use self::Direction::*;
         match *self {
-            C => [0, 0],
-            E => [1, 0],
-            N => [0, 1],
-            W => [-1, 0],
-            S => [0, -1],
-            NE => [1, 1],
-            NW => [-1, 1],
-            SW => [-1, -1],
-            SE => [1, -1],
+            C => [0, 0, 0],
+            E => [1, 0, 0],
+            N => [0, 1, 0],
+            W => [-1, 0, 0],
+            S => [0, -1, 0],
+            NE => [1, 1, 0],
+            NW => [-1, 1, 0],
+            SW => [-1, -1, 0],
+            SE => [1, -1, 0],
+            T => [0, 0, 1],
+            B => [0, 0, -1],
+            ET => [1, 0, 1],
+            WT => [-1, 0, 1],
+            NT => [0, 1, 1],
+            ST => [0, -1, 1],
+            EB => [1, 0, -1],
+            WB => [-1, 0, -1],
+            NB => [0, 1, -1],
+            SB => [0, -1, -1],
+            NET => [1, 1, 1],
+            NWT => [-1, 1, 1],
+            SWT => [-1, -1, 1],
+            SET => [1, -1, 1],
+            NEB => [1, 1, -1],
+            NWB => [-1, 1, -1],
+            SWB => [-1, -1, -1],
+            SEB => [1, -1, -1],
         }
     }
     #[inline(always)]
-    pub fn num_array(&self) -> [num; 2] {
-        let [x, y] = self.array();
-        [num::from(x), num::from(y)]
+    pub fn num_array(&self) -> [f64; 3] {
+        let [x, y, z] = self.array();
+        [f64::from(x), f64::from(y), f64::from(z)]
     }
 }
 
 pub trait Geometry {
-    #[inline(always)]
     fn contains(&self, x: grid::X) -> bool;
+
+    /// Unit normal pointing into the fluid at the point `x` of the geometry
+    /// (if the geometry is a straight, lattice-aligned wall)
+    #[inline(always)]
+    fn normal(&self, _: grid::X) -> Option<[i8; 3]> {
+        None
+    }
+
+    /// Fraction `q` in `[0, 1]` of the link from the point `x` outside the
+    /// geometry to `x + c` at which it enters the geometry (if it does and
+    /// the surface of the geometry is known)
+    #[inline(always)]
+    fn intersection(&self, _x: grid::X, _c: [i8; 3]) -> Option<f64> {
+        None
+    }
 }
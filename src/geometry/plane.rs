@@ -17,9 +17,14 @@ impl Geometry for Plane {
     fn contains(&self, x: grid::X) -> bool {
         match (self.n.0, self.n.1, x.0, x.1) {
             (1, 0, x, _) => !(x > self.x.0),
+            (-1, 0, x, _) => !(x < self.x.0),
             (0, 1, _, y) => !(y > self.x.1),
             (0, -1, _, y) => !(y < self.x.1),
             _ => unimplemented!(),
         }
     }
+    #[inline(always)]
+    fn normal(&self, _: grid::X) -> Option<[i8; 3]> {
+        Some([self.n.0 as i8, self.n.1 as i8, 0])
+    }
 }
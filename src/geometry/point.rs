@@ -1,4 +1,3 @@
-use ::num;
 use std;
 
 pub trait AmbientDimension {
@@ -9,12 +8,12 @@ pub trait ObjectDimension {
     fn object_dimension(&self) -> usize;
 }
 
-pub trait Point: AmbientDimension + ObjectDimension + std::ops::Index<num> {
+pub trait Point: AmbientDimension + ObjectDimension + std::ops::Index<f64> {
 
 }
 
 struct Point2D {
-    x: [num; 2]
+    x: [f64; 2]
 }
 
 #[cfg(test)]
@@ -1,21 +1,45 @@
-use num;
 use grid;
 use super::Geometry;
 
 pub struct Rectangle {
-    x_center: [num; 2],
-    lengths: [num; 2],
+    x_center: [f64; 2],
+    lengths: [f64; 2],
 }
 
 impl Rectangle {
-    pub fn new(x_center: [num; 2], lengths: [num; 2]) -> Self {
+    pub fn new(x_center: [f64; 2], lengths: [f64; 2]) -> Self {
         Self { x_center, lengths }
     }
 }
 
 impl Geometry for Rectangle {
     #[inline(always)]
-    fn contains(&self, _: grid::X) -> bool {
-        unimplemented!()
+    fn contains(&self, x: grid::X) -> bool {
+        let x = [x.0 as f64, x.1 as f64];
+        (0..2).all(|d| {
+            (x[d] - self.x_center[d]).abs() <= 0.5 * self.lengths[d]
+        })
+    }
+    #[inline(always)]
+    fn intersection(&self, x: grid::X, c: [i8; 3]) -> Option<f64> {
+        // entry and exit fractions of the link through the slabs of both
+        // axes:
+        let x = [x.0 as f64, x.1 as f64];
+        let (mut q_in, mut q_out): (f64, f64) = (0., 1.);
+        for d in 0..2 {
+            let lo = self.x_center[d] - 0.5 * self.lengths[d];
+            let hi = self.x_center[d] + 0.5 * self.lengths[d];
+            if c[d] == 0 {
+                if x[d] < lo || x[d] > hi {
+                    return None;
+                }
+                continue;
+            }
+            let c_d = f64::from(c[d]);
+            let (q_0, q_1) = ((lo - x[d]) / c_d, (hi - x[d]) / c_d);
+            q_in = q_in.max(q_0.min(q_1));
+            q_out = q_out.min(q_0.max(q_1));
+        }
+        if q_in <= q_out { Some(q_in) } else { None }
     }
 }
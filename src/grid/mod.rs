@@ -0,0 +1,85 @@
+//! Implements naive structured grids.
+
+use std;
+use rayon;
+
+mod structured_line;
+mod structured_rectangular;
+mod structured_cuboid;
+mod sparse;
+
+pub use self::structured_line::StructuredLine;
+pub use self::structured_rectangular::StructuredRectangular;
+pub use self::structured_cuboid::StructuredCuboid;
+pub use self::sparse::Sparse;
+
+/// Index of a point in the grid.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct Idx(pub usize);
+
+impl Idx {
+    fn new(v: usize) -> Idx {
+        Idx(v)
+    }
+}
+
+/// Coordinates of a point in the grid.
+///
+/// Unused trailing coordinates of lower dimensional grids are zero.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct X(pub usize, pub usize, pub usize);
+
+/// Iterator over the point indices of a grid.
+pub type IdxIterator =
+    std::iter::Map<std::ops::Range<usize>, fn(usize) -> Idx>;
+
+/// Parallel iterator over the point indices of a grid.
+pub type ParIdxIterator =
+    rayon::iter::Map<rayon::range::Iter<usize>, fn(usize) -> Idx>;
+
+/// Structured grid.
+pub trait Grid: Copy + Sync + Send {
+    /// Number of spatial dimensions of the grid
+    fn dimension() -> usize;
+
+    /// Number of points in the grid
+    fn size(&self) -> usize;
+
+    /// Returns the coordinates of a given point id
+    fn x(&self, i: Idx) -> X;
+
+    /// Returns the id of the point at coordinates
+    fn idx(&self, x: X) -> Idx;
+
+    /// Returns the neighbor of the point `c` in direction `dir` (if it
+    /// does not lie across the end of a non-periodic axis)
+    fn neighbor<D: ::Distribution>(&self, c: Idx, dir: D) -> Option<Idx>;
+
+    /// Iterator over all point indices in the grid
+    #[inline(always)]
+    fn ids(&self) -> IdxIterator {
+        (0..self.size()).map(Idx::new)
+    }
+
+    /// Parallel iterator over all point indices in the grid
+    #[inline(always)]
+    fn par_ids(&self) -> ParIdxIterator {
+        use rayon::iter::IntoParallelIterator;
+        use rayon::iter::ParallelIterator;
+        (0..self.size()).into_par_iter().map(Idx::new)
+    }
+}
+
+/// Moves the coordinate `x` by `d` on an axis of length `l`, wrapping
+/// around its ends if the axis is `periodic`.
+#[inline(always)]
+fn shift(x: usize, d: i8, l: usize, periodic: bool) -> Option<usize> {
+    match d {
+        0 => Some(x),
+        1 if x == l - 1 => if periodic { Some(0) } else { None },
+        1 => Some(x + 1),
+        -1 if x == 0 => if periodic { Some(l - 1) } else { None },
+        -1 => Some(x - 1),
+        _ => unreachable!(),
+    }
+}
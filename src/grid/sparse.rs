@@ -0,0 +1,166 @@
+//! Implements a sparse grid with indirect addressing.
+
+use std;
+use std::marker::PhantomData;
+use super::{Grid, Idx, X};
+
+/// Sentinel of a missing neighbor.
+const NONE: u32 = std::u32::MAX;
+
+/// Index of the lattice direction `[d_x, d_y, d_z]` in a table of all 27
+/// directions.
+#[inline(always)]
+fn direction_code(d: [i8; 3]) -> usize {
+    (d[0] + 1) as usize + 3 * (d[1] + 1) as usize + 9 * (d[2] + 1) as usize
+}
+
+/// Sparse grid storing only the points of the grid `G` that are active for
+/// the lattice `D`.
+///
+/// The inactive points are solid points whose neighbors along the links of
+/// `D` are all solid: they are never read by their neighbors. Each active
+/// point stores the indices of its neighbors in a precomputed table.
+///
+/// The grid is used through a reference, e.g. `Solver::new(&sparse, ..)`.
+pub struct Sparse<G: Grid, D: ::Distribution> {
+    grid: G,
+    /// Index in `grid` of the active points (sorted)
+    points: Vec<Idx>,
+    /// Index of the points of `grid` in `points` (`NONE` if inactive)
+    sparse: Vec<u32>,
+    /// Neighbors `neighbors[c * D::size() + n]` of the active points
+    neighbors: Vec<u32>,
+    /// Index of all lattice directions in `D` (if present)
+    directions: [Option<usize>; 27],
+    __dist: PhantomData<D>,
+}
+
+impl<G: Grid, D: ::Distribution> Sparse<G, D> {
+    /// Sparse grid of the points of `grid` that are not `solid(x)` or have
+    /// a neighbor that is not.
+    pub fn new<S: Fn(X) -> bool>(grid: G, solid: S) -> Self {
+        let active = |c: Idx| {
+            !solid(grid.x(c)) ||
+                D::all().any(|n| {
+                    grid.neighbor(c, n).map_or(false, |c| !solid(grid.x(c)))
+                })
+        };
+        let points: Vec<Idx> = grid.ids().filter(|&c| active(c)).collect();
+        assert!(points.len() < NONE as usize);
+        let mut sparse = vec![NONE; grid.size()];
+        for (i, c) in points.iter().enumerate() {
+            sparse[c.0] = i as u32;
+        }
+
+        let mut neighbors = Vec::with_capacity(points.len() * D::size());
+        for &c in &points {
+            for n in D::all() {
+                let c_n = grid.neighbor(c, n).map_or(NONE, |c_n| sparse[c_n.0]);
+                neighbors.push(c_n);
+            }
+        }
+
+        let mut directions = [None; 27];
+        for n in D::all() {
+            directions[direction_code(n.direction().array())] =
+                Some(n.value());
+        }
+
+        Self {
+            grid,
+            points,
+            sparse,
+            neighbors,
+            directions,
+            __dist: PhantomData,
+        }
+    }
+
+    /// Underlying (dense) grid
+    pub fn dense(&self) -> &G {
+        &self.grid
+    }
+}
+
+impl<'a, G: Grid, D: ::Distribution> Grid for &'a Sparse<G, D> {
+    #[inline(always)]
+    fn dimension() -> usize {
+        G::dimension()
+    }
+
+    #[inline(always)]
+    fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns the coordinates of a given point id
+    #[inline(always)]
+    fn x(&self, i: Idx) -> X {
+        self.grid.x(self.points[i.0])
+    }
+
+    /// Returns the id of the point at coordinates
+    ///
+    /// Panics if the point is not active.
+    #[inline(always)]
+    fn idx(&self, x: X) -> Idx {
+        let c = self.sparse[self.grid.idx(x).0];
+        assert!(c != NONE, "the point is not part of the sparse grid");
+        Idx(c as usize)
+    }
+
+    /// Returns the neighbor of the point `c` in direction `dir` (if it is
+    /// active)
+    #[inline(always)]
+    fn neighbor<D2: ::Distribution>(&self, c: Idx, dir: D2) -> Option<Idx> {
+        let n = self.directions[direction_code(dir.direction().array())]
+            .expect("the direction is not part of the lattice of the grid");
+        match self.neighbors[c.0 * D::size() + n] {
+            NONE => None,
+            c_n => Some(Idx(c_n as usize)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D2Q9;
+    use grid::StructuredRectangular;
+    use traits::Distribution;
+
+    #[test]
+    fn neighbors() {
+        // the lowest three rows are solid, the lowest two are inactive:
+        let dense =
+            StructuredRectangular::new(4, 5).with_periodic([true, false]);
+        let g = Sparse::<_, D2Q9>::new(dense, |x| x.1 < 3);
+        let g = &g;
+        assert_eq!(g.size(), 12);
+        assert_eq!(g.size(), g.ids().count());
+        for c in g.ids() {
+            assert_eq!(g.idx(g.x(c)), c);
+            assert!(g.x(c).1 > 1);
+            for n in D2Q9::all() {
+                let c_n = dense.neighbor(dense.idx(g.x(c)), n);
+                match g.neighbor(c, n) {
+                    Some(c_n_) => assert_eq!(Some(dense.idx(g.x(c_n_))), c_n),
+                    None => assert!(c_n.map_or(true, |c| dense.x(c).1 == 1)),
+                }
+            }
+        }
+        let c = g.idx(X(0, 2, 0));
+        assert_eq!(g.neighbor(c, D2Q9::W), Some(g.idx(X(3, 2, 0))));
+        assert_eq!(g.neighbor(c, D2Q9::N), Some(g.idx(X(0, 3, 0))));
+        assert_eq!(g.neighbor(c, D2Q9::S), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "the point is not part of the sparse grid")]
+    fn inactive_idx() {
+        let dense =
+            StructuredRectangular::new(4, 5).with_periodic([true, false]);
+        let g = Sparse::<_, D2Q9>::new(dense, |x| x.1 < 3);
+        (&g).idx(X(0, 1, 0));
+    }
+}
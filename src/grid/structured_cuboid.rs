@@ -0,0 +1,127 @@
+//! Implements a naive three dimensional cuboid grid.
+
+use super::{shift, Grid, Idx, X};
+
+/// Three-dimensional cuboid grid.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct StructuredCuboid {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    /// Is the grid periodic along the x, y, and z axes?
+    periodic: [bool; 3],
+}
+
+impl StructuredCuboid {
+    /// Grid of `x * y * z` points periodic along all axes.
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            periodic: [true, true, true],
+        }
+    }
+    /// Copy of the grid with the periodicity `periodic` of the x, y, and z
+    /// axes.
+    pub fn with_periodic(self, periodic: [bool; 3]) -> Self {
+        Self { periodic, ..self }
+    }
+}
+
+impl Grid for StructuredCuboid {
+    #[inline(always)]
+    fn dimension() -> usize {
+        3
+    }
+
+    #[inline(always)]
+    fn size(&self) -> usize {
+        self.x * self.y * self.z
+    }
+
+    /// Returns the coordinates of a given point id
+    #[inline(always)]
+    fn x(&self, i: Idx) -> X {
+        let xy = self.x * self.y;
+        let z = i.0 / xy;
+        let r = i.0 - z * xy;
+        let y = r / self.x;
+        let x = r - y * self.x;
+        X(x, y, z)
+    }
+
+    /// Returns the id of the point at coordinates
+    #[inline(always)]
+    fn idx(&self, x: X) -> Idx {
+        Idx(x.0 + self.x * (x.1 + self.y * x.2))
+    }
+
+    /// Returns the neighbor of the point `c` in direction `dir`
+    #[inline(always)]
+    fn neighbor<D: ::Distribution>(&self, c: Idx, dir: D) -> Option<Idx> {
+        let X(x_i, y_i, z_i) = self.x(c);
+        let [d_x, d_y, d_z] = dir.direction().array();
+
+        // handle periodic boundaries:
+        let x_n = shift(x_i, d_x, self.x, self.periodic[0])?;
+        let y_n = shift(y_i, d_y, self.y, self.periodic[1])?;
+        let z_n = shift(z_i, d_z, self.z, self.periodic[2])?;
+        Some(self.idx(X(x_n, y_n, z_n)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D3Q19;
+
+    fn test_grid() -> StructuredCuboid {
+        StructuredCuboid::new(4, 3, 2)
+    }
+
+    #[test]
+    fn ids() {
+        let g = test_grid();
+        assert_eq!(g.size(), 24);
+        assert_eq!(g.size(), g.ids().count());
+
+        let mut c = 0;
+        for k in 0..2 {
+            for j in 0..3 {
+                for i in 0..4 {
+                    let x = X(i, j, k);
+                    let idx = Idx(c);
+                    assert_eq!(g.x(idx), x);
+                    assert_eq!(g.idx(x), idx);
+                    c += 1;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn periodic_neighbors() {
+        let g = test_grid();
+        let c = g.idx(X(0, 0, 0));
+        assert_eq!(g.neighbor(c, D3Q19::C), Some(c));
+        assert_eq!(g.neighbor(c, D3Q19::E), Some(g.idx(X(1, 0, 0))));
+        assert_eq!(g.neighbor(c, D3Q19::W), Some(g.idx(X(3, 0, 0))));
+        assert_eq!(g.neighbor(c, D3Q19::S), Some(g.idx(X(0, 2, 0))));
+        assert_eq!(g.neighbor(c, D3Q19::T), Some(g.idx(X(0, 0, 1))));
+        assert_eq!(g.neighbor(c, D3Q19::B), Some(g.idx(X(0, 0, 1))));
+        assert_eq!(g.neighbor(c, D3Q19::SB), Some(g.idx(X(0, 2, 1))));
+        assert_eq!(g.neighbor(c, D3Q19::NE), Some(g.idx(X(1, 1, 0))));
+    }
+    #[test]
+    fn non_periodic_neighbors() {
+        let g = test_grid().with_periodic([false, true, false]);
+        let c = g.idx(X(0, 0, 1));
+        assert_eq!(g.neighbor(c, D3Q19::E), Some(g.idx(X(1, 0, 1))));
+        assert_eq!(g.neighbor(c, D3Q19::S), Some(g.idx(X(0, 2, 1))));
+        assert_eq!(g.neighbor(c, D3Q19::B), Some(g.idx(X(0, 0, 0))));
+        assert_eq!(g.neighbor(c, D3Q19::W), None);
+        assert_eq!(g.neighbor(c, D3Q19::T), None);
+        assert_eq!(g.neighbor(c, D3Q19::ST), None);
+    }
+}
@@ -0,0 +1,83 @@
+//! Implements a naive one dimensional grid.
+
+use super::{shift, Grid, Idx, X};
+
+/// One-dimensional grid.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct StructuredLine {
+    pub x: usize,
+    /// Is the grid periodic along the x axis?
+    periodic: [bool; 1],
+}
+
+impl StructuredLine {
+    /// Periodic grid of `x` points.
+    pub fn new(x: usize) -> Self {
+        Self {
+            x,
+            periodic: [true],
+        }
+    }
+    /// Copy of the grid with the periodicity `periodic` of the x axis.
+    pub fn with_periodic(self, periodic: [bool; 1]) -> Self {
+        Self { periodic, ..self }
+    }
+}
+
+impl Grid for StructuredLine {
+    #[inline(always)]
+    fn dimension() -> usize {
+        1
+    }
+
+    #[inline(always)]
+    fn size(&self) -> usize {
+        self.x
+    }
+
+    /// Returns the coordinates of a given point id
+    #[inline(always)]
+    fn x(&self, i: Idx) -> X {
+        X(i.0, 0, 0)
+    }
+
+    /// Returns the id of the point at coordinates
+    #[inline(always)]
+    fn idx(&self, x: X) -> Idx {
+        Idx(x.0)
+    }
+
+    /// Returns the neighbor of the point `c` in direction `dir`
+    #[inline(always)]
+    fn neighbor<D: ::Distribution>(&self, c: Idx, dir: D) -> Option<Idx> {
+        let [d_x, d_y, d_z] = dir.direction().array();
+        debug_assert!(d_y == 0 && d_z == 0);
+
+        // handle periodic boundaries:
+        shift(c.0, d_x, self.x, self.periodic[0]).map(Idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D1Q3;
+
+    #[test]
+    fn neighbors() {
+        let g = StructuredLine::new(5);
+        assert_eq!(g.size(), g.ids().count());
+        for i in g.ids() {
+            assert_eq!(g.idx(g.x(i)), i);
+        }
+        assert_eq!(g.neighbor(Idx(0), D1Q3::C), Some(Idx(0)));
+        assert_eq!(g.neighbor(Idx(0), D1Q3::E), Some(Idx(1)));
+        assert_eq!(g.neighbor(Idx(0), D1Q3::W), Some(Idx(4)));
+        assert_eq!(g.neighbor(Idx(4), D1Q3::E), Some(Idx(0)));
+
+        let g = g.with_periodic([false]);
+        assert_eq!(g.neighbor(Idx(0), D1Q3::E), Some(Idx(1)));
+        assert_eq!(g.neighbor(Idx(0), D1Q3::W), None);
+        assert_eq!(g.neighbor(Idx(4), D1Q3::E), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Implements a naive two dimensional rectangular grid.
+
+use super::{shift, Grid, Idx, X};
+
+/// Two-dimensional rectangular grid.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Copy, Clone, Debug)]
+pub struct StructuredRectangular {
+    pub x: usize,
+    pub y: usize,
+    /// Is the grid periodic along the x and y axes?
+    periodic: [bool; 2],
+}
+
+impl StructuredRectangular {
+    /// Grid of `x * y` points periodic along both axes.
+    pub fn new(x: usize, y: usize) -> Self {
+        Self {
+            x,
+            y,
+            periodic: [true, true],
+        }
+    }
+    /// Copy of the grid with the periodicity `periodic` of the x and y
+    /// axes.
+    pub fn with_periodic(self, periodic: [bool; 2]) -> Self {
+        Self { periodic, ..self }
+    }
+}
+
+impl Grid for StructuredRectangular {
+    #[inline(always)]
+    fn dimension() -> usize {
+        2
+    }
+
+    #[inline(always)]
+    fn size(&self) -> usize {
+        self.x * self.y
+    }
+
+    /// Returns the coordinates of a given point id
+    #[inline(always)]
+    fn x(&self, i: Idx) -> X {
+        let y = i.0 / self.x;
+        let x = i.0 - y * self.x;
+        X(x, y, 0)
+    }
+
+    /// Returns the id of the point at coordinates
+    #[inline(always)]
+    fn idx(&self, x: X) -> Idx {
+        Idx(x.0 + self.x * x.1)
+    }
+
+    /// Returns the neighbor of the point `c` in direction `dir`
+    #[inline(always)]
+    fn neighbor<D: ::Distribution>(&self, c: Idx, dir: D) -> Option<Idx> {
+        let X(x_i, y_i, _) = self.x(c);
+        let [d_x, d_y, d_z] = dir.direction().array();
+        debug_assert!(d_z == 0);
+
+        // handle periodic boundaries:
+        let x_n = shift(x_i, d_x, self.x, self.periodic[0])?;
+        let y_n = shift(y_i, d_y, self.y, self.periodic[1])?;
+        Some(self.idx(X(x_n, y_n, 0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D2Q9;
+
+    fn test_grid() -> StructuredRectangular {
+        StructuredRectangular::new(4, 3)
+    }
+
+    #[test]
+    fn ids() {
+        let g = test_grid();
+        assert_eq!(g.size(), 12);
+        assert_eq!(g.size(), g.ids().count());
+        let mut c = 0;
+        for i in g.ids() {
+            assert_eq!(i, Idx(c));
+            c += 1;
+        }
+
+        let mut c = 0;
+        for j in 0..3 {
+            for i in 0..4 {
+                let x = X(i, j, 0);
+                let idx = Idx(c);
+                assert_eq!(g.x(idx), x);
+                assert_eq!(g.idx(x), idx);
+                c += 1;
+            }
+        }
+    }
+    #[test]
+    fn non_periodic_neighbors() {
+        let g = test_grid().with_periodic([true, false]);
+        let c = g.idx(X(0, 0, 0));
+        assert_eq!(g.neighbor(c, D2Q9::C), Some(c));
+        assert_eq!(g.neighbor(c, D2Q9::W), Some(g.idx(X(3, 0, 0))));
+        assert_eq!(g.neighbor(c, D2Q9::N), Some(g.idx(X(0, 1, 0))));
+        assert_eq!(g.neighbor(c, D2Q9::NW), Some(g.idx(X(3, 1, 0))));
+        assert_eq!(g.neighbor(c, D2Q9::S), None);
+        assert_eq!(g.neighbor(c, D2Q9::SE), None);
+        let c = g.idx(X(3, 2, 0));
+        assert_eq!(g.neighbor(c, D2Q9::E), Some(g.idx(X(0, 2, 0))));
+        assert_eq!(g.neighbor(c, D2Q9::NE), None);
+    }
+}
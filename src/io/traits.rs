@@ -1,10 +1,9 @@
 //! I/O traits
-use ::num;
 
 /// Point trait TODO: move this to geometry
 pub trait Point {
     fn size() -> usize;
-    fn get(&self, i: usize) -> num;
+    fn get(&self, i: usize) -> f64;
 }
 
 /// Primitive types
@@ -3,7 +3,7 @@ use std::fs::File;
 use std::fmt::Display;
 
 use grid::*;
-use num;
+use Float;
 
 pub trait Primitive: Display {
     fn type_name() -> &'static str;
@@ -30,15 +30,15 @@ impl Primitive for i32 {
 
 pub struct CellDataWriter {
     buffer: File,
-    grid: StructuredRectangular,
+    size: usize,
     init: bool,
 }
 
 impl CellDataWriter {
-    pub fn new(buffer: File, grid: StructuredRectangular) -> CellDataWriter {
+    pub fn new(buffer: File, size: usize) -> CellDataWriter {
         CellDataWriter {
             buffer: buffer,
-            grid: grid,
+            size: size,
             init: false,
         }
     }
@@ -51,7 +51,7 @@ impl CellDataWriter {
         if !self.init {
             // Write cell data
             self.buffer
-                .write(format!("CELL_DATA {}\n", self.grid.size()).as_bytes())
+                .write(format!("CELL_DATA {}\n", self.size).as_bytes())
                 .unwrap();
             self.init = true;
         }
@@ -60,13 +60,14 @@ impl CellDataWriter {
             .write(format!("SCALARS {} {}\n", name, T::type_name()).as_bytes())
             .unwrap();
         self.buffer.write(b"LOOKUP_TABLE default\n").unwrap();
-        for c in self.grid.ids() {
+        for c in (0..self.size).map(Idx) {
             self.buffer.write(format!("{}\n", f(c)).as_bytes()).unwrap();
         }
     }
 }
 
-pub fn write_vtk(fname: &str, grid: StructuredRectangular) -> CellDataWriter {
+/// Writes the grid of the file `fname.vtk` with points of type `T`.
+pub fn write_vtk<T: Float, G: Grid>(fname: &str, grid: G) -> CellDataWriter {
     let mut buffer = File::create(format!("{}.vtk", fname)).unwrap();
 
     // Write Header
@@ -76,45 +77,67 @@ pub fn write_vtk(fname: &str, grid: StructuredRectangular) -> CellDataWriter {
                    DATASET UNSTRUCTURED_GRID\n"
     ).unwrap();
 
-    let x_stencil = [-1., 1., -1., 1.];
-    let y_stencil = [-1., -1., 1., 1.];
+    let x_stencil = [-1., 1., -1., 1., -1., 1., -1., 1.];
+    let y_stencil = [-1., -1., 1., 1., -1., -1., 1., 1.];
+    let z_stencil = [-1., -1., -1., -1., 1., 1., 1., 1.];
     let length = 1.;
 
+    // Lines in 1D, pixels in 2D, voxels in 3D:
+    let d = G::dimension();
+    assert!(
+        d >= 1 && d <= 3,
+        "VTK output of {}-dimensional grids is not supported",
+        d
+    );
+    let (no_cell_points, cell_type) = match d {
+        1 => (2, 3),
+        2 => (4, 8),
+        _ => (8, 11),
+    };
 
     // Write grid points
     buffer
-        .write(format!("POINTS {} FLOAT\n", grid.size() * 4).as_bytes())
+        .write(
+            format!(
+                "POINTS {} {}\n",
+                grid.size() * no_cell_points,
+                T::type_name()
+            ).as_bytes(),
+        )
         .unwrap();
 
     for c in grid.ids() {
-        let X(x, y) = grid.x(c);
-        for i in 0..4 {
-            let xp = x as num + x_stencil[i] * 0.5 * length;
-            let yp = y as num + y_stencil[i] * 0.5 * length;
-            buffer
-                .write(format!("{} {} 0.0\n", xp, yp).as_bytes())
-                .unwrap();
+        let X(x, y, z) = grid.x(c);
+        for i in 0..no_cell_points {
+            let xp = T::from_f64(x as f64 + x_stencil[i] * 0.5 * length);
+            let yp = T::from_f64(y as f64 + y_stencil[i] * 0.5 * length);
+            let zp = T::from_f64(z as f64 + z_stencil[i] * 0.5 * length);
+            let p = match d {
+                1 => format!("{} 0.0 0.0\n", xp),
+                2 => format!("{} {} 0.0\n", xp, yp),
+                _ => format!("{} {} {}\n", xp, yp, zp),
+            };
+            buffer.write(p.as_bytes()).unwrap();
         }
     }
 
     // Write grid cells:
     buffer
         .write(
-            format!("CELLS {} {}\n", grid.size(), grid.size() * 5).as_bytes(),
+            format!(
+                "CELLS {} {}\n",
+                grid.size(),
+                grid.size() * (no_cell_points + 1)
+            ).as_bytes(),
         )
         .unwrap();
     for c in grid.ids() {
-        buffer
-            .write(
-                format!(
-                    "4 {} {} {} {}\n",
-                    4 * c.0,
-                    4 * c.0 + 1,
-                    4 * c.0 + 2,
-                    4 * c.0 + 3
-                ).as_bytes(),
-            )
-            .unwrap();
+        let mut line = format!("{}", no_cell_points);
+        for i in 0..no_cell_points {
+            line.push_str(&format!(" {}", no_cell_points * c.0 + i));
+        }
+        line.push('\n');
+        buffer.write(line.as_bytes()).unwrap();
     }
 
 
@@ -122,8 +145,73 @@ pub fn write_vtk(fname: &str, grid: StructuredRectangular) -> CellDataWriter {
         .write(format!("CELL_TYPES {}\n", grid.size()).as_bytes())
         .unwrap();
     for _ in grid.ids() {
-        buffer.write(b"8\n").unwrap();
+        buffer.write(format!("{}\n", cell_type).as_bytes()).unwrap();
     }
 
-    CellDataWriter::new(buffer, grid)
+    CellDataWriter::new(buffer, grid.size())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use distribution::D2Q9;
+    use grid::{Sparse, StructuredRectangular};
+
+    /// Writes the scalar `x + 100 y` of the cells of `grid` to the file
+    /// `fname`.vtk in the temporary directory and reads it back as a map
+    /// from the cell centers to the values.
+    fn write_and_read<G: Grid>(fname: &str, grid: G) -> BTreeMap<X, f64> {
+        let path = ::std::env::temp_dir().join(fname);
+        let path = path.to_str().unwrap();
+        write_vtk::<f64, _>(path, grid).write_scalar("xy", |c| {
+            let X(x, y, _) = grid.x(c);
+            (x + 100 * y) as f64
+        });
+
+        let mut s = String::new();
+        File::open(format!("{}.vtk", path))
+            .unwrap()
+            .read_to_string(&mut s)
+            .unwrap();
+        ::std::fs::remove_file(format!("{}.vtk", path)).unwrap();
+        let lines: Vec<&str> = s.lines().collect();
+        let section = |name: &str| {
+            lines.iter().position(|l| l.starts_with(name)).unwrap()
+        };
+        let points = section("POINTS");
+        let values = section("LOOKUP_TABLE");
+        let mut cells = BTreeMap::new();
+        for c in 0..grid.size() {
+            let mut center = [0.; 2];
+            for p in 0..4 {
+                let p: Vec<f64> = lines[points + 1 + 4 * c + p]
+                    .split(' ')
+                    .map(|v| v.parse().unwrap())
+                    .collect();
+                center[0] += 0.25 * p[0];
+                center[1] += 0.25 * p[1];
+            }
+            let x = X(center[0] as usize, center[1] as usize, 0);
+            let v = lines[values + 1 + c].parse().unwrap();
+            assert!(cells.insert(x, v).is_none());
+        }
+        cells
+    }
+
+    /// The cells of a sparse grid are written at the positions and with
+    /// the values of the same cells of the dense grid.
+    #[test]
+    fn sparse_matches_dense() {
+        let dense = StructuredRectangular::new(4, 16);
+        let sparse = Sparse::<_, D2Q9>::new(dense, |x| x.1 < 4 || x.1 > 12);
+        let d = write_and_read("lbm_rs_vtk_dense", dense);
+        let s = write_and_read("lbm_rs_vtk_sparse", &sparse);
+        assert_eq!(d.len(), 4 * 16);
+        assert_eq!(s.len(), 4 * 11);
+        for (x, v) in &s {
+            assert!(x.1 >= 3 && x.1 <= 13);
+            assert_eq!(d[x], *v);
+        }
+    }
 }
@@ -0,0 +1,140 @@
+//! Memory layouts of the populations of a grid.
+
+/// Memory layout of the `q` populations of each of `n` cells in a single
+/// array.
+///
+/// # Safety
+///
+/// The solver writes the populations through raw pointers from several
+/// threads. For all cells `c < n` and populations `i < q`, `idx(c, i, n, q)`
+/// must be smaller than `len(n, q)`, and distinct `(c, i)` must have
+/// distinct indices.
+pub unsafe trait Layout: Send + Sync {
+    /// Length of the array
+    #[inline(always)]
+    fn len(n: usize, q: usize) -> usize {
+        n * q
+    }
+    /// Index of the population `i` of the cell `c` in the array
+    fn idx(c: usize, i: usize, n: usize, q: usize) -> usize;
+}
+
+/// Array of structures: the populations of each cell are contiguous.
+#[derive(Copy, Clone, Debug)]
+pub struct AoS;
+
+unsafe impl Layout for AoS {
+    #[inline(always)]
+    fn idx(c: usize, i: usize, _n: usize, q: usize) -> usize {
+        c * q + i
+    }
+}
+
+/// Structure of arrays: each population of all cells is contiguous.
+#[derive(Copy, Clone, Debug)]
+pub struct SoA;
+
+unsafe impl Layout for SoA {
+    #[inline(always)]
+    fn idx(c: usize, i: usize, n: usize, _q: usize) -> usize {
+        i * n + c
+    }
+}
+
+/// Asserts that the layout `L` maps the populations of `n` cells with `q`
+/// populations each one-to-one into its array.
+pub fn assert_one_to_one<L: Layout>(n: usize, q: usize) {
+    let len = L::len(n, q);
+    let mut used = vec![false; len];
+    for c in 0..n {
+        for i in 0..q {
+            let k = L::idx(c, i, n, q);
+            assert!(
+                k < len && !used[k],
+                "population {} of the cell {} has the invalid index {}",
+                i,
+                c,
+                k
+            );
+            used[k] = true;
+        }
+    }
+}
+
+/// Length of the array of blocks of `b` cells
+#[inline(always)]
+fn blocked_len(n: usize, q: usize, b: usize) -> usize {
+    (n + b - 1) / b * b * q
+}
+
+/// Index in the array of blocks of `b` cells, each a structure of arrays
+#[inline(always)]
+fn blocked_idx(c: usize, i: usize, q: usize, b: usize) -> usize {
+    (c / b) * b * q + i * b + c % b
+}
+
+/// Array of structures of arrays of 4 cells: each population of the 4
+/// cells of a block is contiguous.
+#[derive(Copy, Clone, Debug)]
+pub struct AoSoA4;
+
+unsafe impl Layout for AoSoA4 {
+    #[inline(always)]
+    fn len(n: usize, q: usize) -> usize {
+        blocked_len(n, q, 4)
+    }
+    #[inline(always)]
+    fn idx(c: usize, i: usize, _n: usize, q: usize) -> usize {
+        blocked_idx(c, i, q, 4)
+    }
+}
+
+/// Array of structures of arrays of 8 cells: each population of the 8
+/// cells of a block is contiguous.
+#[derive(Copy, Clone, Debug)]
+pub struct AoSoA8;
+
+unsafe impl Layout for AoSoA8 {
+    #[inline(always)]
+    fn len(n: usize, q: usize) -> usize {
+        blocked_len(n, q, 8)
+    }
+    #[inline(always)]
+    fn idx(c: usize, i: usize, _n: usize, q: usize) -> usize {
+        blocked_idx(c, i, q, 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts() {
+        for &n in &[1, 7, 8, 13] {
+            assert_one_to_one::<AoS>(n, 9);
+            assert_one_to_one::<SoA>(n, 9);
+            assert_one_to_one::<AoSoA4>(n, 9);
+            assert_one_to_one::<AoSoA8>(n, 19);
+        }
+        assert_eq!(AoS::idx(2, 1, 5, 9), 19);
+        assert_eq!(SoA::idx(2, 1, 5, 9), 7);
+        assert_eq!(AoSoA4::idx(5, 2, 7, 9), 36 + 8 + 1);
+        assert_eq!(AoSoA4::len(5, 9), 72);
+    }
+
+    /// Layout mapping all populations to the same index
+    struct Aliasing;
+
+    unsafe impl Layout for Aliasing {
+        fn idx(_c: usize, _i: usize, _n: usize, _q: usize) -> usize {
+            0
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid index")]
+    fn aliasing_layout() {
+        assert_one_to_one::<Aliasing>(2, 9);
+    }
+}
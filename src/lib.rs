@@ -1,16 +1,15 @@
 #![feature(conservative_impl_trait)]
 #![feature(slice_patterns)]
+#![feature(portable_simd)]
+#![feature(associated_type_defaults)]
 
 #![cfg_attr(feature = "cargo-clippy", allow(inline_always))]
 
 extern crate time;
 pub extern crate rayon;
 
-// TODO: parametrize on floating point type (currently num)
-
-#[allow(non_camel_case_types)]
-type num = f64;
-
+mod float;
+pub use float::{Float, Vector};
 
 mod traits;
 use traits::*;
@@ -21,8 +20,10 @@ pub use traits::DistributionStorage;
 pub mod physics;
 pub mod distribution;
 mod solver;
-pub use solver::Solver;
+pub use solver::{Propagation, Solver};
+pub mod layout;
 pub mod geometry;
 pub mod grid;
 pub mod boundary;
+pub mod force;
 pub mod io;
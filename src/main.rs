@@ -7,21 +7,55 @@ type Dist = distribution::D2Q9;
 type Col = physics::ns::SingleRelaxationTime;
 type Physics = physics::NavierStokes<Dist, Col>;
 
+const INFLOW_VELOCITY: f64 = 0.05;
+
 fn main() {
     // Initialize the grid, physical parameters, and solver:
-    let grid = grid::StructuredRectangular { x: 300, y: 150 };
+    let grid = grid::StructuredRectangular::new(300, 150)
+        .with_periodic([false, false]);
     let physics: Physics = Physics::new(0.1, 0.015, Col { omega: 1.85 });
-    let mut s = lbm::Solver::new(grid, physics);
+    let mut s: lbm::Solver<_, _> = lbm::Solver::new(grid, physics);
 
     // Add Boundary Conditions:
     {
-        // Cylinder:
+        // Cylinder, logging its drag and lift coefficients:
         {
+            let circle = geometry::Circle::new(grid.x, grid.y);
+            let [x_c, y_c] = circle.center();
+            let reference = force::Reference::new(
+                physics.inflow_density,
+                INFLOW_VELOCITY,
+                2. * circle.radius(),
+            );
+            s.forces = Some(
+                force::Monitor::new([x_c, y_c, 0.], reference)
+                    .with_csv("lbm_rs_forces"),
+            );
             let cyl = Box::new(boundary::Condition::new(
-                boundary::Type::BounceBack,
-                geometry::Circle::new(grid.x, grid.y),
+                boundary::Type::InterpolatedBounceBack(
+                    boundary::Interpolation::Bouzidi,
+                ),
+                circle,
             ));
-            s.bcs.push(cyl);
+            s.add_condition(cyl);
+        }
+        // Velocity inlet:
+        {
+            let inlet = Box::new(
+                boundary::Condition::with_velocity(
+                    geometry::Plane::new((1, 0), (0, 0)),
+                    |_, _| [INFLOW_VELOCITY, 0., 0.],
+                ),
+            );
+            s.add_condition(inlet);
+        }
+        // Convective outlet:
+        {
+            let outlet = Box::new(boundary::Condition::new(
+                boundary::Type::Convective(INFLOW_VELOCITY),
+                geometry::Plane::new((-1, 0), (grid.x - 1, 0)),
+            ));
+            s.add_condition(outlet);
         }
         // Bottom channel wall:
         {
@@ -29,7 +63,7 @@ fn main() {
                 boundary::Type::BounceBack,
                 geometry::Plane::new((0, 1), (0, 0)),
             ));
-            s.bcs.push(bottom_wall);
+            s.add_condition(bottom_wall);
         }
         // Top channel wall:
         {
@@ -37,18 +71,7 @@ fn main() {
                 boundary::Type::BounceBack,
                 geometry::Plane::new((0, -1), (0, grid.y - 1)),
             ));
-            s.bcs.push(top_wall);
-        }
-        // Periodic forced inflow:
-        {
-            let bc = Box::new(boundary::Condition::new(
-                boundary::Type::Inflow(
-                    physics.inflow_density,
-                    physics.inflow_accel,
-                ),
-                geometry::Plane::new((1, 0), (0, 0)),
-            ));
-            s.bcs.push(bc);
+            s.add_condition(top_wall);
         }
     }
 
@@ -0,0 +1,156 @@
+//! Advection-diffusion physics
+use std;
+use Float;
+use io::vtk;
+use grid;
+
+/// Advection-diffusion of a passive scalar with a constant advection
+/// velocity using a single relaxation time collision.
+#[derive(Copy, Clone)]
+pub struct AdvectionDiffusion<D: ::Distribution> {
+    pub omega: f64,
+    pub velocity: [f64; 3],
+    __dist: std::marker::PhantomData<D>,
+}
+
+impl<D: ::Distribution> AdvectionDiffusion<D> {
+    pub fn new(omega: f64, velocity: [f64; 3]) -> Self {
+        Self {
+            omega,
+            velocity,
+            __dist: std::marker::PhantomData {},
+        }
+    }
+
+    /// Diffusion coefficient: `c_s^2 (1 / omega - 1 / 2)`
+    #[inline(always)]
+    pub fn diffusivity(&self) -> f64 {
+        D::c_squ() * (1. / self.omega - 0.5)
+    }
+
+    #[inline(always)]
+    pub fn concentration<T: Float, F: Fn(D) -> T>(f: F) -> T {
+        let mut c = T::default();
+        for n in D::all() {
+            c += f(n);
+        }
+        c
+    }
+
+    /// Equilibrium distribution of the direction `n` for concentration `c`
+    #[inline(always)]
+    pub fn equilibrium<T: Float>(&self, n: D, c: T) -> T {
+        let v = n.direction().num_array();
+        let u_n = v[0] * self.velocity[0] + v[1] * self.velocity[1] +
+            v[2] * self.velocity[2];
+        T::from_f64(n.constant()) * c * T::from_f64(1. + u_n / D::c_squ())
+    }
+}
+
+impl<D: ::Distribution> ::traits::Physics for AdvectionDiffusion<D> {
+    type Distribution = D;
+    #[inline(always)]
+    fn collision<T, H, IH>(
+        &self,
+        f_hlp: &H,
+        idx_h: IH,
+        _: grid::X,
+        _: usize,
+    ) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        let f_h = |n| idx_h(f_hlp, n);
+        let c = Self::concentration(&f_h);
+        let omega = T::from_f64(self.omega);
+        let mut r = D::Storage::<T>::default();
+        for n in D::all() {
+            r.as_mut()[n.value()] =
+                f_h(n) + omega * (self.equilibrium(n, c) - f_h(n));
+        }
+        r
+    }
+    /// Equilibrium of the concentration `c` (advected with the constant
+    /// velocity of the physics instead of `u`)
+    #[inline(always)]
+    fn equilibrium<T: Float>(&self, c: T, _u: [T; 3]) -> D::Storage<T> {
+        let mut r = D::Storage::<T>::default();
+        for n in D::all() {
+            r.as_mut()[n.value()] = AdvectionDiffusion::equilibrium(self, n, c);
+        }
+        r
+    }
+    #[inline(always)]
+    fn integral<T: Float, F: Fn(D) -> T>(f: F) -> T {
+        Self::concentration(f)
+    }
+
+    fn write<T, X, O, F>(
+        &self,
+        mut vtk_writer: vtk::CellDataWriter,
+        _: usize,
+        _: X,
+        _: O,
+        f: F,
+    ) -> vtk::CellDataWriter
+    where
+        T: Float,
+        X: Fn(grid::Idx) -> grid::X,
+        F: Fn(grid::Idx, D) -> T,
+        O: Fn(grid::Idx) -> bool,
+    {
+        vtk_writer.write_scalar("c", |c| Self::concentration(|n| f(c, n)));
+        vtk_writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D1Q3;
+    use grid::Grid;
+    use traits::Distribution;
+    use Solver;
+
+    /// Diffusion of a Gaussian pulse: the variance grows as
+    /// `sigma_0^2 + 2 D t` and the mass is conserved.
+    #[test]
+    fn gaussian_diffusion() {
+        let grid = grid::StructuredLine::new(256);
+        let physics = AdvectionDiffusion::<D1Q3>::new(1.2, [0., 0., 0.]);
+        let (x_0, sigma_0, n_it) = (128., 8., 400);
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        s.initialize(|x| {
+            let r = x.0 as f64 - x_0;
+            let c = (-r * r / (2. * sigma_0 * sigma_0)).exp();
+            let mut fs = [0.; 3];
+            for n in D1Q3::all() {
+                fs[n.value()] = physics.equilibrium(n, c);
+            }
+            fs
+        });
+        s.run(n_it, 0);
+
+        let c: Vec<f64> = grid.ids()
+            .map(|i| AdvectionDiffusion::<D1Q3>::concentration(|n| s.f(i, n)))
+            .collect();
+        let mass: f64 = c.iter().sum();
+        let mean: f64 = c.iter()
+            .enumerate()
+            .map(|(x, c)| x as f64 * c)
+            .sum::<f64>() / mass;
+        let variance: f64 = c.iter()
+            .enumerate()
+            .map(|(x, c)| (x as f64 - mean).powi(2) * c)
+            .sum::<f64>() / mass;
+
+        let mass_0 = sigma_0 * (2. * std::f64::consts::PI).sqrt();
+        let variance_0 =
+            sigma_0 * sigma_0 + 2. * physics.diffusivity() * n_it as f64;
+        assert!((mass - mass_0).abs() < 1e-10 * mass_0);
+        assert!((mean - x_0).abs() < 1e-10);
+        assert!((variance - variance_0).abs() < 1e-3 * variance_0);
+    }
+}
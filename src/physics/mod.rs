@@ -1,2 +1,4 @@
+pub mod ad;
 pub mod ns;
+pub use self::ad::AdvectionDiffusion;
 pub use self::ns::NavierStokes;
@@ -0,0 +1,517 @@
+//! Cumulant collision
+use Float;
+use distribution;
+use super::{Distribution, ViscousCollision};
+
+/// Lattices whose populations can be collided with cumulants.
+///
+/// The populations are embedded in the velocities `{-1, 0, 1}^3` with
+/// zeros on the velocities missing from the lattice. `fold` maps the
+/// collided populations of the missing velocities back onto the lattice
+/// keeping the moments the lattice spans.
+///
+/// With equal rates, the collision matches the BGK collision of `D3Q15`
+/// and `D3Q19` only up to terms quadratic in the velocity, which their BGK
+/// equilibria lack in the fourth order moments.
+pub trait CumulantDistribution: Distribution {
+    /// Adds the populations of the velocities missing from the lattice to
+    /// those of the lattice keeping the moments of the lattice
+    #[inline(always)]
+    fn fold<T: Float>(_g: &mut Moments<T>) {}
+}
+
+impl CumulantDistribution for distribution::D2Q9 {}
+impl CumulantDistribution for distribution::D3Q27 {}
+
+/// The corner velocities are missing.
+impl CumulantDistribution for distribution::D3Q19 {
+    #[inline(always)]
+    fn fold<T: Float>(g: &mut Moments<T>) {
+        fold_corners(g);
+    }
+}
+
+/// The edge velocities are missing.
+impl CumulantDistribution for distribution::D3Q15 {
+    #[inline(always)]
+    fn fold<T: Float>(g: &mut Moments<T>) {
+        fold_edges(g);
+    }
+}
+
+/// Moments or cumulants indexed by their exponents in `x`, `y`, and `z`
+/// (or populations indexed by their velocity plus one).
+pub type Moments<T> = [[[T; 3]; 3]; 3];
+
+/// Cumulant collision algorithm.
+///
+/// The populations are transformed to central moments and then to
+/// cumulants, which are relaxed towards their equilibrium (`c_s^2` for the
+/// diagonal second-order cumulants, zero otherwise) with a rate per order,
+/// and transformed back.
+#[derive(Copy, Clone)]
+pub struct Cumulant {
+    /// Rate of the deviatoric and off-diagonal second-order cumulants (sets
+    /// the kinematic viscosity like the `omega` of `SingleRelaxationTime`)
+    pub omega: f64,
+    /// Rate of the trace of the second-order cumulants (sets the bulk
+    /// viscosity)
+    pub bulk: f64,
+    /// Rate of the third-order cumulants
+    pub third: f64,
+    /// Rate of the fourth-order cumulants
+    pub fourth: f64,
+    /// Rate of the fifth-order cumulants
+    pub fifth: f64,
+    /// Rate of the sixth-order cumulants
+    pub sixth: f64,
+}
+
+impl Cumulant {
+    /// Cumulant collision with all rates equal to `omega`.
+    pub fn new(omega: f64) -> Self {
+        Self {
+            omega,
+            bulk: omega,
+            third: omega,
+            fourth: omega,
+            fifth: omega,
+            sixth: omega,
+        }
+    }
+}
+
+impl ViscousCollision for Cumulant {
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self { omega, ..*self }
+    }
+}
+
+/// Transforms populations along the axis `d` to central moments.
+#[inline(always)]
+fn central_moments<T: Float>(g: &mut Moments<T>, d: usize, u: T) {
+    for i in 0..3 {
+        for j in 0..3 {
+            let at = |k: usize| match d {
+                0 => (k, i, j),
+                1 => (i, k, j),
+                _ => (i, j, k),
+            };
+            let (a, b, c) = at(0);
+            let f_m = g[a][b][c];
+            let (a, b, c) = at(1);
+            let f_0 = g[a][b][c];
+            let (a, b, c) = at(2);
+            let f_p = g[a][b][c];
+
+            let m_0 = f_m + f_0 + f_p;
+            let m_1 = f_p - f_m;
+            let m_2 = f_p + f_m;
+            let two = T::from_f64(2.);
+            let k = [m_0, m_1 - u * m_0, m_2 - two * u * m_1 + u * u * m_0];
+            for (e, k) in k.iter().enumerate() {
+                let (a, b, c) = at(e);
+                g[a][b][c] = *k;
+            }
+        }
+    }
+}
+
+/// Transforms central moments along the axis `d` back to populations.
+#[inline(always)]
+fn populations<T: Float>(g: &mut Moments<T>, d: usize, u: T) {
+    for i in 0..3 {
+        for j in 0..3 {
+            let at = |k: usize| match d {
+                0 => (k, i, j),
+                1 => (i, k, j),
+                _ => (i, j, k),
+            };
+            let t = T::from_f64;
+            let mut k = [T::default(); 3];
+            for (e, k) in k.iter_mut().enumerate() {
+                let (a, b, c) = at(e);
+                *k = g[a][b][c];
+            }
+
+            let m_0 = k[0];
+            let m_1 = k[1] + u * k[0];
+            let m_2 = k[2] + t(2.) * u * k[1] + u * u * k[0];
+            let f = [t(0.5) * (m_2 - m_1), m_0 - m_2, t(0.5) * (m_2 + m_1)];
+            for (e, f) in f.iter().enumerate() {
+                let (a, b, c) = at(e);
+                g[a][b][c] = *f;
+            }
+        }
+    }
+}
+
+/// Adds `w` times the population `g_v` to the velocity `v` of `g`.
+#[inline(always)]
+fn add<T: Float>(g: &mut Moments<T>, v: [isize; 3], w: f64, g_v: T) {
+    let [x, y, z] = v;
+    g[(x + 1) as usize][(y + 1) as usize][(z + 1) as usize] +=
+        T::from_f64(w) * g_v;
+}
+
+/// Folds the populations of the corner velocities onto the velocities with
+/// a zero component, keeping the moments `x^a y^b z^c` with a zero
+/// exponent (those of `D3Q19`).
+///
+/// By inclusion-exclusion, a corner is the sum of its projections on the
+/// coordinate planes, minus those on the axes, plus the rest velocity.
+#[inline(always)]
+fn fold_corners<T: Float>(g: &mut Moments<T>) {
+    for &x in &[-1, 1] {
+        for &y in &[-1, 1] {
+            for &z in &[-1, 1] {
+                let g_c = g[(x + 1) as usize][(y + 1) as usize]
+                    [(z + 1) as usize];
+                // the components kept by the projections:
+                for kept in 0..7 {
+                    let k = |d: usize, c: isize| (kept >> d & 1) as isize * c;
+                    let w = match (kept as u32).count_ones() {
+                        1 => -1.,
+                        _ => 1.,
+                    };
+                    add(g, [k(0, x), k(1, y), k(2, z)], w, g_c);
+                }
+            }
+        }
+    }
+}
+
+/// Folds the populations of the edge velocities onto the rest, axis, and
+/// corner velocities, keeping the moments of `D3Q15`: the density, the
+/// momentum, the second moments, `xyz`, `x (y^2 + z^2)` and its
+/// permutations, and `x^2 y^2 + y^2 z^2 + z^2 x^2`.
+#[inline(always)]
+fn fold_edges<T: Float>(g: &mut Moments<T>) {
+    // the edge `a e_p + b e_q` normal to the axis `r`:
+    for r in 0..3 {
+        let (p, q) = ((r + 1) % 3, (r + 2) % 3);
+        for &a in &[-1, 1] {
+            for &b in &[-1, 1] {
+                let v = |c_p: isize, c_q: isize, c_r: isize| {
+                    let mut v = [0; 3];
+                    v[p] = c_p;
+                    v[q] = c_q;
+                    v[r] = c_r;
+                    v
+                };
+                let [x, y, z] = v(a, b, 0);
+                let g_e = g[(x + 1) as usize][(y + 1) as usize]
+                    [(z + 1) as usize];
+                for &s in &[-1, 1] {
+                    for &t in &[-1, 1] {
+                        let w = match s + t {
+                            2 => 7. / 24.,
+                            0 => -1. / 12.,
+                            _ => 1. / 24.,
+                        };
+                        add(g, v(s * a, t * b, 1), w, g_e);
+                        add(g, v(s * a, t * b, -1), w, g_e);
+                    }
+                    let w = if s > 0 { 7. / 12. } else { 1. / 12. };
+                    add(g, v(s * a, 0, 0), w, g_e);
+                    add(g, v(0, s * b, 0), w, g_e);
+                    add(g, v(0, 0, s), -1. / 6., g_e);
+                }
+                add(g, [0, 0, 0], -1. / 3., g_e);
+            }
+        }
+    }
+}
+
+/// Binomial coefficient for `n, k <= 2`.
+#[inline(always)]
+fn binomial(n: usize, k: usize) -> f64 {
+    if k == 1 && n == 2 { 2. } else { 1. }
+}
+
+/// Applies the moment-cumulant recursion to the multi-index `e`:
+///
+/// `m(e) = c(e) + sum_{b <= g, b != g} binom(g, b) c(b + e_k) m(g - b)`
+///
+/// where `g = e - e_k` for the first axis `k` with a non-zero exponent.
+/// Returns the sum.
+#[inline(always)]
+fn recursion<T: Float>(e: [usize; 3], c: &Moments<T>, m: &Moments<T>) -> T {
+    let k = if e[0] > 0 {
+        0
+    } else if e[1] > 0 {
+        1
+    } else {
+        2
+    };
+    let mut g = e;
+    g[k] -= 1;
+    let mut sum = T::default();
+    for b_0 in 0..(g[0] + 1) {
+        for b_1 in 0..(g[1] + 1) {
+            for b_2 in 0..(g[2] + 1) {
+                let b = [b_0, b_1, b_2];
+                if b == g {
+                    continue;
+                }
+                let mut be = b;
+                be[k] += 1;
+                let binom = binomial(g[0], b_0) * binomial(g[1], b_1) *
+                    binomial(g[2], b_2);
+                sum += T::from_f64(binom) * c[be[0]][be[1]][be[2]] *
+                    m[g[0] - b_0][g[1] - b_1][g[2] - b_2];
+            }
+        }
+    }
+    sum
+}
+
+impl<D: CumulantDistribution> ::Collision<D> for Cumulant {
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let u = D::velocities(&f_h);
+        let t = T::from_f64;
+
+        // normalized central moments:
+        let mut m: Moments<T> = [[[T::default(); 3]; 3]; 3];
+        for n in D::all() {
+            let [x, y, z] = n.direction().array();
+            m[(x + 1) as usize][(y + 1) as usize][(z + 1) as usize] =
+                f_h(n) / dloc;
+        }
+        for d in 0..3 {
+            central_moments(&mut m, d, u[d]);
+        }
+
+        // cumulants (in lexicographic order of the exponents):
+        let mut c: Moments<T> = [[[T::default(); 3]; 3]; 3];
+        for a in 0..3 {
+            for b in 0..3 {
+                for d in 0..3 {
+                    if a + b + d > 0 {
+                        c[a][b][d] = m[a][b][d] - recursion([a, b, d], &c, &m);
+                    }
+                }
+            }
+        }
+
+        // relaxation step, second order: trace and deviatoric parts
+        let dim = D::dimension();
+        let dim_c_squ = t(dim as f64 * D::c_squ());
+        let diag = [c[2][0][0], c[0][2][0], c[0][0][2]];
+        let trace: T = diag[..dim].iter().cloned().sum();
+        let trace = dim_c_squ + t(1. - self.bulk) * (trace - dim_c_squ);
+        let mut diag_ = [T::default(); 3];
+        for d in 0..dim {
+            let dev = diag[d] - diag[0];
+            diag_[d] = t(1. - self.omega) * dev;
+        }
+        let mean =
+            (trace - diag_[..dim].iter().cloned().sum::<T>()) / t(dim as f64);
+        c[2][0][0] = mean + diag_[0];
+        c[0][2][0] = mean + diag_[1];
+        if dim == 3 {
+            c[0][0][2] = mean + diag_[2];
+        }
+        c[1][1][0] *= t(1. - self.omega);
+        c[1][0][1] *= t(1. - self.omega);
+        c[0][1][1] *= t(1. - self.omega);
+
+        // higher orders:
+        for a in 0..3 {
+            for b in 0..3 {
+                for d in 0..3 {
+                    let omega = match a + b + d {
+                        0 | 1 | 2 => continue,
+                        3 => self.third,
+                        4 => self.fourth,
+                        5 => self.fifth,
+                        _ => self.sixth,
+                    };
+                    c[a][b][d] *= t(1. - omega);
+                }
+            }
+        }
+
+        // back to central moments and populations:
+        m = [[[T::default(); 3]; 3]; 3];
+        m[0][0][0] = t(1.);
+        for a in 0..3 {
+            for b in 0..3 {
+                for d in 0..3 {
+                    if a + b + d > 0 {
+                        m[a][b][d] = c[a][b][d] + recursion([a, b, d], &c, &m);
+                    }
+                }
+            }
+        }
+        for d in 0..3 {
+            populations(&mut m, d, u[d]);
+        }
+        D::fold(&mut m);
+
+        let mut r = D::Storage::<T>::default();
+        for n in D::all() {
+            let [x, y, z] = n.direction().array();
+            r.as_mut()[n.value()] =
+                dloc * m[(x + 1) as usize][(y + 1) as usize][(z + 1) as usize];
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::{D2Q9, D3Q15, D3Q19, D3Q27};
+    use physics::ns::{self, assert_recovers_srt, SingleRelaxationTime};
+    use Collision;
+
+    /// Populations at velocity `u` (times the velocity direction) with a
+    /// non-equilibrium perturbation of relative size `eps`.
+    fn populations<D: Distribution>(u: f64, eps: f64) -> D::Storage<f64> {
+        let u_z = if D::dimension() == 3 { 0.25 * u } else { 0. };
+        ns::populations::<D>(1., [u, -0.5 * u, u_z], eps)
+    }
+
+    /// Maximum difference between the cumulant and the BGK collisions.
+    fn difference<D>(omega: f64, u: f64, eps: f64) -> f64
+    where
+        D: CumulantDistribution,
+    {
+        let f = populations::<D>(u, eps);
+        let f = f.as_ref();
+        let r_c = Cumulant::new(omega).collision(&f, |f, n: D| f[n.value()]);
+        let r_s = SingleRelaxationTime { omega }
+            .collision(&f, |f, n: D| f[n.value()]);
+        let mut diff: f64 = 0.;
+        for n in D::all() {
+            let d = (r_c.as_ref()[n.value()] - r_s.as_ref()[n.value()]).abs();
+            diff = diff.max(d);
+        }
+        diff
+    }
+
+    /// Conserves mass and momentum.
+    fn conservation<D: CumulantDistribution>() {
+        let f = populations::<D>(0.1, 0.05);
+        let f = f.as_ref();
+        let col = Cumulant {
+            omega: 1.8,
+            bulk: 1.,
+            third: 1.,
+            fourth: 1.2,
+            fifth: 1.,
+            sixth: 1.,
+        };
+        let r = col.collision(&f, |f, n: D| f[n.value()]);
+        let r = r.as_ref();
+        let rho_0 = D::density(|n| f[n.value()]);
+        let rho = D::density(|n| r[n.value()]);
+        assert!((rho - rho_0).abs() < 1e-14);
+        let u_0 = D::velocities(|n| f[n.value()]);
+        let u = D::velocities(|n| r[n.value()]);
+        for d in 0..3 {
+            assert!((u[d] - u_0[d]).abs() < 1e-14);
+        }
+    }
+
+    /// With all rates equal to omega the cumulant collision matches BGK up
+    /// to the terms BGK truncates: of order `u_order` in the velocity (the
+    /// BGK equilibrium is a second order expansion) and quadratic in the
+    /// non-equilibrium part (cumulants are non-linear in the populations).
+    fn equal_rates_match_bgk<D: CumulantDistribution>(u_order: i32) {
+        for &omega in &[0.8, 1.0, 1.7] {
+            assert!(difference::<D>(omega, 0., 0.) < 1e-15);
+            let mut previous = (
+                difference::<D>(omega, 0.02, 0.),
+                difference::<D>(omega, 0., 0.02),
+            );
+            assert!(previous.0 < 0.02f64.powi(u_order) && previous.1 < 1e-4);
+            for i in 1..4 {
+                let s = 0.02 / (2 as f64).powi(i);
+                let d = (
+                    difference::<D>(omega, s, 0.),
+                    difference::<D>(omega, 0., s),
+                );
+                assert!(d.0 < previous.0 / (0.875 * 2f64.powi(u_order)));
+                if omega == 1. {
+                    // the non-equilibrium part is fully relaxed
+                    assert!(d.1 < 1e-15);
+                } else {
+                    assert!(d.1 < previous.1 / 3.5);
+                }
+                previous = d;
+            }
+        }
+    }
+
+    /// With all rates equal to omega the cumulant collision relaxes the
+    /// momentum flux exactly like BGK, and reproduces BGK at rest with a
+    /// normal stress along x, whose cumulants relax linearly.
+    fn equal_rates_reproduce_bgk<D: CumulantDistribution>() {
+        for &omega in &[0.8, 1.7] {
+            let f = populations::<D>(0.05, 0.05);
+            let f = f.as_ref();
+            let r_c =
+                Cumulant::new(omega).collision(&f, |f, n: D| f[n.value()]);
+            let r_s = SingleRelaxationTime { omega }
+                .collision(&f, |f, n: D| f[n.value()]);
+            for a in 0..3 {
+                for b in 0..3 {
+                    let flux = |r: &D::Storage<f64>| {
+                        D::all().fold(0., |s, n| {
+                            let c = n.direction().num_array();
+                            s + c[a] * c[b] * r.as_ref()[n.value()]
+                        })
+                    };
+                    assert!((flux(&r_c) - flux(&r_s)).abs() < 1e-15);
+                }
+            }
+
+            let mut f = D::equilibrium(1., [0., 0., 0.]);
+            for n in D::all() {
+                let c_x = n.direction().num_array()[0];
+                let p = 0.05 * (c_x * c_x / D::c_squ() - 1.);
+                f.as_mut()[n.value()] *= 1. + p;
+            }
+            assert_recovers_srt::<D, _>(Cumulant::new(omega), omega, &f);
+        }
+    }
+
+    #[test]
+    fn d2q9() {
+        conservation::<D2Q9>();
+        equal_rates_match_bgk::<D2Q9>(3);
+        equal_rates_reproduce_bgk::<D2Q9>();
+    }
+
+    /// The BGK equilibria of the lattices without the corner or the edge
+    /// velocities miss fourth order moments quadratic in the velocity.
+    #[test]
+    fn d3q15_d3q19() {
+        conservation::<D3Q15>();
+        equal_rates_match_bgk::<D3Q15>(2);
+        equal_rates_reproduce_bgk::<D3Q15>();
+        conservation::<D3Q19>();
+        equal_rates_match_bgk::<D3Q19>(2);
+        equal_rates_reproduce_bgk::<D3Q19>();
+    }
+
+    #[test]
+    fn d3q27() {
+        conservation::<D3Q27>();
+        equal_rates_match_bgk::<D3Q27>(3);
+        equal_rates_reproduce_bgk::<D3Q27>();
+    }
+}
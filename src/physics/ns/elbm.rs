@@ -0,0 +1,293 @@
+//! Entropic lattice Boltzmann (ELBM) collision
+use Float;
+use traits::Counts;
+use super::{Distribution, ViscousCollision};
+
+/// Populations closer than this (relative) to equilibrium use `alpha = 2`.
+const NEAR_EQUILIBRIUM: f64 = 1e-3;
+/// Relative deviation of `alpha` from 2 counted as a deviation.
+const DEVIATION: f64 = 1e-3;
+/// Convergence tolerance of the Newton iteration.
+const TOLERANCE: f64 = 1e-12;
+/// Maximum number of Newton iterations.
+const MAX_ITERATIONS: usize = 20;
+
+/// Counters of the entropic collision.
+///
+/// Each block of cells is counted separately and the solver merges the
+/// counters until it writes a step line.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct EntropicStep {
+    /// Number of collided cells
+    pub cells: usize,
+    /// Number of cells with `alpha` different from 2
+    pub deviated: usize,
+    /// Number of cells in which the Newton iteration failed
+    pub fallback: usize,
+}
+
+impl Counts for EntropicStep {
+    #[inline(always)]
+    fn merge(&mut self, other: Self) {
+        self.cells += other.cells;
+        self.deviated += other.deviated;
+        self.fallback += other.fallback;
+    }
+}
+
+/// Entropic single relaxation time algorithm.
+///
+/// Relaxes `f + alpha * omega / 2 * (f_eq - f)` where the over-relaxation
+/// parameter `alpha` is the non-trivial root of the entropy condition
+/// `H(f + alpha (f_eq - f)) = H(f)` with `H(f) = sum_i f_i ln(f_i / w_i)`.
+#[derive(Copy, Clone)]
+pub struct Entropic {
+    pub omega: f64,
+}
+
+/// Discrete H-function of `f + alpha * df`.
+#[inline(always)]
+fn h<D: Distribution>(f: &[f64], df: &[f64], alpha: f64) -> f64 {
+    let mut h = 0.;
+    for n in D::all() {
+        let f = f[n.value()] + alpha * df[n.value()];
+        h += f * (f / n.constant()).ln();
+    }
+    h
+}
+
+/// Derivative of `h` with respect to `alpha`.
+#[inline(always)]
+fn dh<D: Distribution>(f: &[f64], df: &[f64], alpha: f64) -> f64 {
+    let mut dh = 0.;
+    for n in D::all() {
+        let f_ = f[n.value()] + alpha * df[n.value()];
+        dh += df[n.value()] * ((f_ / n.constant()).ln() + 1.);
+    }
+    dh
+}
+
+impl Entropic {
+    /// Solves the entropy condition for `alpha` given the populations `f`
+    /// and their distance to equilibrium `df = f_eq - f`.
+    ///
+    /// Returns `None` if no root within the positivity bound is found.
+    #[inline(always)]
+    pub fn alpha<D: Distribution>(f: &[f64], df: &[f64]) -> Option<f64> {
+        // positivity bound: f + alpha df > 0
+        let mut alpha_max = ::std::f64::INFINITY;
+        let mut deviation: f64 = 0.;
+        for n in D::all() {
+            let (f, df) = (f[n.value()], df[n.value()]);
+            if !(f > 0.) {
+                return None;
+            }
+            if df < 0. {
+                alpha_max = alpha_max.min(-f / df);
+            }
+            deviation = deviation.max((df / f).abs());
+        }
+        if deviation < NEAR_EQUILIBRIUM {
+            return Some(2.);
+        }
+
+        let h_0 = h::<D>(f, df, 0.);
+        let mut alpha: f64 = 2.;
+        alpha = alpha.min(0.5 * (1. + alpha_max));
+        for _ in 0..MAX_ITERATIONS {
+            let d = dh::<D>(f, df, alpha);
+            if d == 0. {
+                return None;
+            }
+            let step = (h::<D>(f, df, alpha) - h_0) / d;
+            alpha -= step;
+            if !(alpha > 1. && alpha < alpha_max) {
+                return None;
+            }
+            if step.abs() < TOLERANCE * alpha {
+                return Some(alpha);
+            }
+        }
+        None
+    }
+}
+
+impl ViscousCollision for Entropic {
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self { omega }
+    }
+}
+
+impl<D: Distribution> ::Collision<D> for Entropic {
+    type Counts = EntropicStep;
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        self.counted_collision(f_hlp, idx_h, &mut EntropicStep::default())
+    }
+
+    #[inline(always)]
+    fn counted_collision<T, H, IH>(
+        &self,
+        f_hlp: &H,
+        idx_h: IH,
+        counts: &mut EntropicStep,
+    ) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let u = D::velocities(&f_h);
+
+        // distance to equilibrium (`alpha` is always solved in `f64`):
+        let mut f_ = D::Storage::<f64>::default();
+        let mut df_ = D::Storage::<f64>::default();
+        let f_equ = D::equilibrium(dloc, u);
+        for n in D::all() {
+            let f_n = f_h(n);
+            f_.as_mut()[n.value()] = f_n.to_f64();
+            df_.as_mut()[n.value()] =
+                (f_equ.as_ref()[n.value()] - f_n).to_f64();
+        }
+        let (f, df) = (f_.as_ref(), df_.as_ref());
+
+        let alpha = match Self::alpha::<D>(f, df) {
+            Some(alpha) => alpha,
+            None => {
+                // fall back to half the positivity bound `-f / df`, not
+                // larger than the BGK over-relaxation:
+                counts.fallback += 1;
+                let mut alpha: f64 = 2.;
+                for n in D::all() {
+                    let (f, df) = (f[n.value()], df[n.value()]);
+                    if df < 0. && f > 0. {
+                        alpha = alpha.min(-0.5 * f / df);
+                    }
+                }
+                alpha
+            }
+        };
+        counts.cells += 1;
+        if (alpha - 2.).abs() > 2. * DEVIATION {
+            counts.deviated += 1;
+        }
+
+        // relaxation step:
+        let beta = 0.5 * self.omega;
+        let mut r = D::Storage::<T>::default();
+        for n in D::all() {
+            r.as_mut()[n.value()] =
+                T::from_f64(f[n.value()] + alpha * beta * df[n.value()]);
+        }
+        r
+    }
+
+    fn statistics(&self, s: EntropicStep) -> Option<String> {
+        let percent = |v: usize| 100. * v as f64 / (s.cells.max(1)) as f64;
+        Some(format!(
+            "alpha != 2: {:.2}% | alpha fallback: {:.2}%",
+            percent(s.deviated),
+            percent(s.fallback)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D2Q9;
+    use physics::ns::SingleRelaxationTime;
+    use traits::Distribution;
+    use Collision;
+
+    #[test]
+    fn near_equilibrium_recovers_srt() {
+        let mut counts = EntropicStep::default();
+        let omega = 1.9;
+        let elbm = Entropic { omega };
+        let srt = SingleRelaxationTime { omega };
+        let mut f = [0.; 9];
+        for n in D2Q9::all() {
+            f[n.value()] = n.constant() * (1. + 1e-5 * n.value() as f64);
+        }
+        let r_elbm: [f64; 9] =
+            elbm.counted_collision(&f, |f, n: D2Q9| f[n.value()], &mut counts);
+        let r_srt: [f64; 9] = srt.collision(&f, |f, n: D2Q9| f[n.value()]);
+        for i in 0..9 {
+            assert!((r_elbm[i] - r_srt[i]).abs() < 1e-15);
+        }
+        assert_eq!(
+            counts,
+            EntropicStep {
+                cells: 1,
+                deviated: 0,
+                fallback: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn entropy_does_not_increase() {
+        let mut counts = EntropicStep::default();
+        let elbm = Entropic { omega: 1.99 };
+        let mut f = [0.; 9];
+        for n in D2Q9::all() {
+            f[n.value()] = n.constant() * (1. + 0.3 * (n.value() % 4) as f64);
+        }
+        let r: [f64; 9] =
+            elbm.counted_collision(&f, |f, n: D2Q9| f[n.value()], &mut counts);
+
+        let zero = [0.; 9];
+        let h_0 = h::<D2Q9>(&f, &zero, 0.);
+        let h_1 = h::<D2Q9>(&r, &zero, 0.);
+        assert!(h_1 <= h_0);
+
+        use physics::ns::Distribution as NsDistribution;
+        let rho_0 = D2Q9::density(|n| f[n.value()]);
+        let rho = D2Q9::density(|n| r[n.value()]);
+        assert!((rho - rho_0).abs() < 1e-14);
+        let u_0 = D2Q9::velocities(|n| f[n.value()]);
+        let u = D2Q9::velocities(|n| r[n.value()]);
+        for d in 0..2 {
+            assert!((u[d] - u_0[d]).abs() < 1e-14);
+        }
+
+        assert_eq!(counts.cells, 1);
+        assert_eq!(counts.deviated, 1);
+        assert_eq!(counts.fallback, 0);
+    }
+
+    #[test]
+    fn lanes_count_their_cells() {
+        use traits::{Lanes, LANES};
+        let elbm = Entropic { omega: 1.9 };
+        let mut f: [Lanes; 9] = [[0.; LANES]; 9];
+        for n in D2Q9::all() {
+            for k in 0..LANES {
+                f[n.value()][k] = n.constant();
+            }
+        }
+        let mut counts = EntropicStep::default();
+        Collision::<D2Q9>::collision_lanes(&elbm, &mut f, 5, &mut counts);
+        let mut other = counts;
+        other.fallback = 2;
+        counts.merge(other);
+        assert_eq!(
+            counts,
+            EntropicStep {
+                cells: 10,
+                deviated: 0,
+                fallback: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,1202 @@
+//! Navier-Stokes physics
+use std;
+use Float;
+use io::vtk;
+use grid;
+use distribution;
+
+mod cumulant;
+mod elbm;
+mod mrt;
+mod regularized;
+mod smagorinsky;
+mod srt_simd;
+mod trt;
+pub use self::cumulant::{Cumulant, CumulantDistribution};
+pub use self::elbm::{Entropic, EntropicStep};
+pub use self::mrt::MultipleRelaxationTime;
+pub use self::regularized::{RecursiveRegularized, Regularized};
+pub use self::smagorinsky::Smagorinsky;
+pub use self::trt::TwoRelaxationTime;
+
+/// Navier-Stokes distributions:
+pub trait Distribution: ::DirectDistribution + ::DiagonalDistribution {
+    #[inline(always)]
+    fn density<T: Float, F: Fn(Self) -> T>(f: F) -> T {
+        let mut rho = T::default();
+        for n in Self::all() {
+            rho += f(n);
+        }
+        rho
+    }
+
+    #[inline(always)]
+    fn pressure<T: Float, F: Fn(Self) -> T>(f: F) -> T {
+        Self::density(f) * T::from_f64(Self::c_squ())
+    }
+
+    #[inline(always)]
+    fn velocity<T: Float, F: Fn(Self) -> T>(f: F, d: usize) -> T {
+        let mut tmp = T::default();
+        for n in Self::all() {
+            tmp += T::from_f64(n.direction().num_array()[d]) * f(n);
+        }
+        tmp / Self::density(f)
+    }
+
+    #[inline(always)]
+    fn velocities<T: Float, F: Fn(Self) -> T>(f: F) -> [T; 3] {
+        [
+            Self::velocity(&f, 0),
+            Self::velocity(&f, 1),
+            Self::velocity(&f, 2),
+        ]
+    }
+
+    /// Equilibrium distribution functions for the density `dloc` and the
+    /// velocity `u`
+    #[inline(always)]
+    fn equilibrium<T: Float>(dloc: T, u: [T; 3]) -> Self::Storage<T> {
+        let [u_x, u_y, u_z] = u;
+        let t = T::from_f64;
+        let c_squ = t(Self::c_squ());
+
+        // n- velocity compnents (n = grid node connection vectors)
+        // TODO: switch to 3 speeds only
+        let mut u_n_ = Self::Storage::<T>::default();
+        let u_n = u_n_.as_mut();
+        for n in Self::all() {
+            let v = n.direction().num_array();
+            let n = n.value();
+            u_n[n] = t(v[0]) * u_x + t(v[1]) * u_y + t(v[2]) * u_z;
+        }
+
+        // equilibrium densities:
+        let f0 = t(2.) * c_squ * c_squ;
+        let f1 = t(2.) * c_squ;
+        // square velocity
+        let u_squ = u_x.powf(t(2.)) + u_y.powf(t(2.)) + u_z.powf(t(2.));
+        let f2 = u_squ / f1;
+
+        let mut n_equ_ = Self::Storage::<T>::default();
+        {
+            let n_equ = n_equ_.as_mut();
+
+            // zero-th velocity density
+            n_equ[0] = t(Self::center().constant()) * dloc * (t(1.) - f2);
+
+            for n in Self::direct() {
+                let f3 = t(n.constant()) * dloc;
+                let n = n.value();
+                n_equ[n] = f3 *
+                    (t(1.) + u_n[n] / c_squ + u_n[n].powf(t(2.)) / f0 - f2);
+            }
+            for n in Self::diagonal() {
+                let f4 = t(n.constant()) * dloc;
+                let n = n.value();
+                n_equ[n] = f4 *
+                    (t(1.) + u_n[n] / c_squ + u_n[n].powf(t(2.)) / f0 - f2);
+            }
+        }
+        n_equ_
+    }
+}
+
+/// Non-equilibrium second-order moment
+/// `Pi_ab = sum_i c_ia c_ib (f_i - f_eq_i)`.
+#[inline(always)]
+fn non_equilibrium_stress<T: Float, D: Distribution>(
+    f: &[T],
+    f_equ: &[T],
+) -> [[T; 3]; 3] {
+    let mut pi = [[T::default(); 3]; 3];
+    for n in D::all() {
+        let c = n.direction().num_array();
+        let f_neq = f[n.value()] - f_equ[n.value()];
+        for a in 0..3 {
+            for b in 0..3 {
+                pi[a][b] += T::from_f64(c[a] * c[b]) * f_neq;
+            }
+        }
+    }
+    pi
+}
+
+/// Guo forcing term
+/// `w_i [(c_i - u) / c_s^2 + (c_i . u) c_i / c_s^4] . F` of the body force
+/// `F` at the velocity `u`.
+#[inline(always)]
+fn guo_forcing<T: Float, D: Distribution>(
+    u: [T; 3],
+    force: [T; 3],
+) -> D::Storage<T> {
+    let t = T::from_f64;
+    let c_squ = t(D::c_squ());
+    let mut s = D::Storage::<T>::default();
+    for n in D::all() {
+        let c = n.direction().num_array();
+        let c = [t(c[0]), t(c[1]), t(c[2])];
+        let c_u = c[0] * u[0] + c[1] * u[1] + c[2] * u[2];
+        let mut s_n = T::default();
+        for d in 0..3 {
+            s_n += ((c[d] - u[d]) / c_squ + c_u * c[d] / (c_squ * c_squ)) *
+                force[d];
+        }
+        s.as_mut()[n.value()] = t(n.constant()) * s_n;
+    }
+    s
+}
+
+/// Body force density `F(x, n_it)` acting on the cell `x` during the
+/// iteration `n_it`.
+pub trait BodyForce
+    : Fn(grid::X, usize) -> [f64; 3] + Copy + Sync + Send {
+}
+
+impl<T> BodyForce for T
+where
+    T: Fn(grid::X, usize) -> [f64; 3] + Copy + Sync + Send,
+{
+}
+
+/// Body force type of `NavierStokes` physics without a body force.
+pub type NoForce = fn(grid::X, usize) -> [f64; 3];
+
+/// Collisions whose kinematic viscosity `c_s^2 (1 / omega - 1 / 2)` is set
+/// by a single relaxation rate `omega`.
+pub trait ViscousCollision: Copy {
+    /// Relaxation rate setting the kinematic viscosity
+    fn omega(&self) -> f64;
+    /// Copy of the collision with the relaxation rate `omega`
+    fn with_omega(&self, omega: f64) -> Self;
+}
+
+impl Distribution for distribution::D2Q9 {}
+impl Distribution for distribution::D3Q15 {}
+impl Distribution for distribution::D3Q19 {}
+impl Distribution for distribution::D3Q27 {}
+
+/// Single relaxation time (SRT) algorithm
+#[derive(Copy, Clone)]
+pub struct SingleRelaxationTime {
+    pub omega: f64,
+}
+
+impl ViscousCollision for SingleRelaxationTime {
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self { omega }
+    }
+}
+
+impl<D: Distribution> ::Collision<D> for SingleRelaxationTime {
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let u = D::velocities(&f_h);
+
+        // equilibrium densities:
+        let n_equ_ = D::equilibrium(dloc, u);
+        let n_equ = n_equ_.as_ref();
+
+        // relaxation step:
+        let omega = T::from_f64(self.omega);
+        let mut r = D::Storage::<T>::default();
+        for n in D::all() {
+            r.as_mut()[n.value()] =
+                f_h(n) + omega * (n_equ[n.value()] - f_h(n));
+        }
+        r
+    }
+
+    /// Collides the lanes with the widest SIMD kernel supported by the CPU
+    /// (if any).
+    #[inline(always)]
+    fn collision_lanes<T: Float>(
+        &self,
+        f: &mut [::Lanes<T>],
+        len: usize,
+        counts: &mut (),
+    ) {
+        let omega = T::from_f64(self.omega);
+        if !srt_simd::collision::<T, D>(omega, f, len, srt_simd::width()) {
+            ::traits::scalar_collision_lanes::<T, D, _>(self, f, len, counts);
+        }
+    }
+}
+
+/// Navier-Stokes physics with the collision `C` and an optional body force
+/// `B`.
+///
+/// The body force is applied with Guo's forcing scheme: the collision acts
+/// on the populations shifted by half the forcing term, so that their
+/// velocity includes half the force, and the other half of the forcing
+/// term is added to the result. For `SingleRelaxationTime` this is exactly
+/// `f_i - omega (f_i - f_eq_i) + (1 - omega / 2) S_i`.
+#[derive(Copy, Clone)]
+pub struct NavierStokes<D, C, B = NoForce>
+where
+    D: Distribution,
+    C: ::Collision<D>,
+    B: BodyForce,
+{
+    pub inflow_density: f64,
+    pub inflow_accel: f64,
+    collision: C,
+    force: Option<B>,
+    __dist: std::marker::PhantomData<D>,
+}
+
+
+impl<D: Distribution, C: ::Collision<D>> NavierStokes<D, C> {
+    pub fn new(density: f64, accel: f64, col: C) -> Self {
+        Self {
+            inflow_density: density,
+            inflow_accel: accel,
+            collision: col,
+            force: None,
+            __dist: std::marker::PhantomData {},
+        }
+    }
+}
+
+impl<D, C, B> NavierStokes<D, C, B>
+where
+    D: Distribution,
+    C: ::Collision<D>,
+    B: BodyForce,
+{
+    /// Copy of the physics with the body force `force`.
+    pub fn with_force<F: BodyForce>(self, force: F) -> NavierStokes<D, C, F> {
+        NavierStokes {
+            inflow_density: self.inflow_density,
+            inflow_accel: self.inflow_accel,
+            collision: self.collision,
+            force: Some(force),
+            __dist: std::marker::PhantomData {},
+        }
+    }
+    /// Body force acting on the cell `x` during the iteration `n_it`
+    #[inline(always)]
+    pub fn force(&self, x: grid::X, n_it: usize) -> [f64; 3] {
+        match self.force {
+            Some(force) => force(x, n_it),
+            None => [0., 0., 0.],
+        }
+    }
+    #[inline(always)]
+    pub fn pressure<T, F>(&self, solid: bool, f: F) -> T
+    where
+        T: Float,
+        F: Fn(D) -> T,
+    {
+        if solid {
+            T::from_f64(self.inflow_density * D::c_squ())
+        } else {
+            D::pressure(f)
+        }
+    }
+    #[inline(always)]
+    pub fn velocities<T: Float, F: Fn(D) -> T>(solid: bool, f: F) -> [T; 3] {
+        if solid {
+            [T::default(); 3]
+        } else {
+            D::velocities(f)
+        }
+    }
+    /// Velocity of the populations `f` of the cell `x` after the collision
+    /// of the iteration `n_it`.
+    ///
+    /// The collision adds the body force to the momentum of the
+    /// populations; the fluid velocity lies half-way, so half of the force
+    /// is removed again.
+    #[inline(always)]
+    pub fn post_collision_velocities<T: Float, F: Fn(D) -> T>(
+        &self,
+        solid: bool,
+        x: grid::X,
+        n_it: usize,
+        f: F,
+    ) -> [T; 3] {
+        let mut u = Self::velocities(solid, &f);
+        if !solid && self.force.is_some() {
+            let force = self.force(x, n_it);
+            let dloc = D::density(&f);
+            for d in 0..3 {
+                u[d] -= T::from_f64(0.5 * force[d]) / dloc;
+            }
+        }
+        u
+    }
+    /// Collision of the cell `x` during the iteration `n_it` counted in
+    /// `counts`
+    #[inline(always)]
+    fn counted_collision<T, H, IH>(
+        &self,
+        f_hlp: &H,
+        idx_h: IH,
+        x: grid::X,
+        n_it: usize,
+        counts: &mut C::Counts,
+    ) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        let force = match self.force {
+            Some(force) => force(x, n_it),
+            None => {
+                return self.collision.counted_collision(f_hlp, idx_h, counts)
+            }
+        };
+        let t = T::from_f64;
+        let force = [t(force[0]), t(force[1]), t(force[2])];
+
+        // velocity including half the force:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let mut u = D::velocities(&f_h);
+        for d in 0..3 {
+            u[d] += t(0.5) * force[d] / dloc;
+        }
+
+        // Guo forcing, half before and half after the collision:
+        let s_ = guo_forcing::<T, D>(u, force);
+        let s = s_.as_ref();
+        let mut f = D::Storage::<T>::default();
+        for n in D::all() {
+            f.as_mut()[n.value()] = f_h(n) + t(0.5) * s[n.value()];
+        }
+        let mut r = self.collision.counted_collision(
+            &f,
+            |f, n| f.as_ref()[n.value()],
+            counts,
+        );
+        for n in D::all() {
+            r.as_mut()[n.value()] += t(0.5) * s[n.value()];
+        }
+        r
+    }
+}
+
+impl<D, C, B> ::traits::Physics for NavierStokes<D, C, B>
+where
+    D: Distribution,
+    C: ::Collision<D>,
+    B: BodyForce,
+{
+    type Distribution = D;
+    type Counts = C::Counts;
+    #[inline(always)]
+    fn collision<T, H, IH>(
+        &self,
+        f_hlp: &H,
+        idx_h: IH,
+        x: grid::X,
+        n_it: usize,
+    ) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        let mut counts = C::Counts::default();
+        self.counted_collision(f_hlp, idx_h, x, n_it, &mut counts)
+    }
+    #[inline(always)]
+    fn collision_lanes<T: Float>(
+        &self,
+        f: &mut [::Lanes<T>],
+        x: &[grid::X],
+        n_it: usize,
+        counts: &mut C::Counts,
+    ) {
+        if self.force.is_none() {
+            return self.collision.collision_lanes(f, x.len(), counts);
+        }
+        for (k, &x) in x.iter().enumerate() {
+            let r = self.counted_collision(
+                &f,
+                |f, n: D| f[n.value()][k],
+                x,
+                n_it,
+                counts,
+            );
+            for n in D::all() {
+                f[n.value()][k] = r.as_ref()[n.value()];
+            }
+        }
+    }
+    #[inline(always)]
+    fn equilibrium<T: Float>(&self, rho: T, u: [T; 3]) -> D::Storage<T> {
+        D::equilibrium(rho, u)
+    }
+    #[inline(always)]
+    fn integral<T: Float, F: Fn(D) -> T>(f: F) -> T {
+        D::density(f)
+    }
+
+    fn statistics(&self, counts: C::Counts) -> Option<String> {
+        self.collision.statistics(counts)
+    }
+
+    fn write<T, X, O, F>(
+        &self,
+        mut vtk_writer: vtk::CellDataWriter,
+        n_it: usize,
+        x: X,
+        obst: O,
+        f: F,
+    ) -> vtk::CellDataWriter
+    where
+        T: Float,
+        X: Fn(grid::Idx) -> grid::X,
+        F: Fn(grid::Idx, D) -> T,
+        O: Fn(grid::Idx) -> bool,
+    {
+        let u = |c| {
+            self.post_collision_velocities(obst(c), x(c), n_it, |n| f(c, n))
+        };
+        vtk_writer.write_scalar("p", |c| self.pressure(obst(c), |n| f(c, n)));
+        vtk_writer.write_scalar("u", |c| u(c)[0]);
+        vtk_writer.write_scalar("v", |c| u(c)[1]);
+        if D::dimension() == 3 {
+            vtk_writer.write_scalar("w", |c| u(c)[2]);
+        }
+        self.collision.write(vtk_writer, obst, f)
+    }
+}
+
+/// Test populations: the equilibrium of the density `rho` and the
+/// velocity `u` plus a non-equilibrium perturbation of relative size `eps`.
+#[cfg(test)]
+pub fn populations<D: Distribution>(
+    rho: f64,
+    u: [f64; 3],
+    eps: f64,
+) -> D::Storage<f64> {
+    let mut f = D::equilibrium(rho, u);
+    for n in D::all() {
+        let [x, y, z] = n.direction().array();
+        let p = eps * n.constant() * (x * y + y * z - x * x) as f64;
+        f.as_mut()[n.value()] += p;
+    }
+    f
+}
+
+/// Asserts that the collision `col` of the populations `f` is the single
+/// relaxation time one with the rate `omega`.
+#[cfg(test)]
+pub fn assert_recovers_srt<D, C>(col: C, omega: f64, f: &D::Storage<f64>)
+where
+    D: Distribution,
+    C: ::Collision<D>,
+{
+    use Collision;
+    let f = f.as_ref();
+    let r = col.collision(&f, |f, n: D| f[n.value()]);
+    let r_srt = SingleRelaxationTime { omega }
+        .collision(&f, |f, n: D| f[n.value()]);
+    for n in D::all() {
+        let n = n.value();
+        assert!((r.as_ref()[n] - r_srt.as_ref()[n]).abs() < 1e-15);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boundary;
+    use geometry;
+    use grid::Grid;
+    use traits::Distribution as LatticeDistribution;
+    use distribution::D2Q9;
+    use layout::{self, AoS, Layout};
+    use {Propagation, Solver};
+
+    /// Populations stream along their lattice direction: without collision
+    /// (`omega = 0`), a population added to a cell is found one cell
+    /// downstream after an iteration.
+    #[test]
+    fn streaming_direction() {
+        let grid = grid::StructuredRectangular::new(8, 1);
+        let col = SingleRelaxationTime { omega: 0. };
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        let east = D2Q9::from_direction(geometry::Direction::E).unwrap();
+        s.initialize(|x| {
+            let mut f = D2Q9::equilibrium(1., [0., 0., 0.]);
+            if x.0 == 3 {
+                f[east.value()] += 0.1;
+            }
+            f
+        });
+        s.run(1, 0);
+        for c in grid.ids() {
+            let f_e = D2Q9::equilibrium(1., [0., 0., 0.])[east.value()];
+            let excess = s.f(c, east) - f_e;
+            let expected = if grid.x(c).0 == 4 { 0.1 } else { 0. };
+            assert!((excess - expected).abs() < 1e-15);
+        }
+    }
+
+    /// Body force along x driving the flows of `channel`
+    const G: f64 = 1e-6;
+
+    /// Relaxation rate of the shear stress of the flows of `channel`
+    const OMEGA: f64 = 1.;
+
+    type ChannelPhysics = NavierStokes<D2Q9, TwoRelaxationTime>;
+
+    /// Flow of `grid` driven by the body force `G` along x between the
+    /// conditions `walls` (type, and normal and point of their plane),
+    /// initially at rest. Returns the physics with the solver.
+    fn channel(
+        grid: grid::StructuredRectangular,
+        walls: &[(boundary::Type, (isize, isize), (usize, usize))],
+    ) -> (ChannelPhysics, Solver<ChannelPhysics>) {
+        let col = TwoRelaxationTime::with_magic_parameter(OMEGA, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col)
+            .with_force::<NoForce>(|_, _| [G, 0., 0.]);
+        let mut s = Solver::new(grid, physics);
+        for &(wall_type, normal, x) in walls {
+            s.add_condition(Box::new(boundary::Condition::new(
+                wall_type,
+                geometry::Plane::new(normal, x),
+            )));
+        }
+        s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        (physics, s)
+    }
+
+    /// Channel flow driven by a constant body force between bounce-back
+    /// walls located half-way between the wall and the fluid cells
+    /// approaches the parabolic Poiseuille profile.
+    #[test]
+    fn poiseuille() {
+        use boundary::Type::BounceBack;
+        let grid = grid::StructuredRectangular::new(3, 12);
+        let (physics, mut s) = channel(grid, &[
+            (BounceBack, (0, 1), (0, 0)),
+            (BounceBack, (0, -1), (0, grid.y - 1)),
+        ]);
+        s.run(4000, 0);
+
+        let nu = D2Q9::c_squ() * (1. / OMEGA - 0.5);
+        let (y_0, y_1) = (0.5, grid.y as f64 - 1.5);
+        let u_max = G / (8. * nu) * (y_1 - y_0).powi(2);
+        for c in grid.ids() {
+            let x = grid.x(c);
+            if x.1 == 0 || x.1 == grid.y - 1 {
+                continue;
+            }
+            let y = x.1 as f64;
+            let u = physics.post_collision_velocities(false, x, 0, |n| {
+                s.f(c, n)
+            });
+            let expected = G / (2. * nu) * (y - y_0) * (y_1 - y);
+            assert!((u[0] - expected).abs() < 1e-6 * u_max);
+            assert!(u[1].abs() < 1e-6 * u_max);
+        }
+    }
+
+    /// The walls of a channel flow driven by a body force balance it.
+    #[test]
+    fn wall_loads() {
+        use boundary::Type::BounceBack;
+        use force;
+        let grid = grid::StructuredRectangular::new(3, 12);
+        let (_, mut s) = channel(grid, &[
+            (BounceBack, (0, 1), (0, 0)),
+            (BounceBack, (0, -1), (0, grid.y - 1)),
+        ]);
+        let reference = force::Reference::new(1., 0.01, grid.y as f64 - 2.);
+        s.forces = Some(force::Monitor::new([0., 0., 0.], reference));
+        // the cell flags are up to date before the first iteration:
+        assert!(s.loads([0., 0., 0.])[0].force[1] < 0.);
+        s.run(4000, 0);
+
+        let loads = s.forces.as_ref().unwrap().loads();
+        assert_eq!(loads.len(), 2);
+        assert_eq!(loads, &s.loads([0., 0., 0.])[..]);
+        let f_x = G * (grid.x * (grid.y - 2)) as f64;
+        for l in loads {
+            assert!((l.force[0] - 0.5 * f_x).abs() < 1e-6 * f_x);
+        }
+        assert!((loads[0].force[1] + loads[1].force[1]).abs() < 1e-12);
+    }
+
+    /// The loads of a cell contained by several solid conditions act on
+    /// the last one, which sets its populations.
+    #[test]
+    fn overlapping_wall_loads() {
+        use boundary::Type::BounceBack;
+        use force;
+        let grid = grid::StructuredRectangular::new(3, 12);
+        let (_, mut s) = channel(grid, &[
+            (BounceBack, (0, 1), (0, 0)),
+            (BounceBack, (0, -1), (0, grid.y - 1)),
+            (BounceBack, (0, 1), (0, 0)),
+        ]);
+        let reference = force::Reference::new(1., 0.01, grid.y as f64 - 2.);
+        s.forces = Some(force::Monitor::new([0., 0., 0.], reference));
+        s.run(100, 0);
+
+        let loads = s.forces.as_ref().unwrap().loads();
+        assert_eq!(loads[0], force::Load::default());
+        assert!(loads[2].force[0] > 0.);
+        assert!((loads[1].force[0] - loads[2].force[0]).abs() < 1e-15);
+    }
+
+    /// A channel flow between thick walls on a sparse grid without the
+    /// inner wall cells matches the one on the dense grid.
+    #[test]
+    fn sparse_channel() {
+        let dense = grid::StructuredRectangular::new(4, 16);
+        let wall = |x: grid::X| x.1 < 4 || x.1 > 12;
+        let sparse = grid::Sparse::<_, D2Q9>::new(dense, &wall);
+        let sparse = &sparse;
+        assert_eq!(sparse.size(), 4 * 11);
+
+        let g = 1e-6;
+        let col = TwoRelaxationTime::with_magic_parameter(1., 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col)
+            .with_force(move |_, _| [g, 0., 0.]);
+        let bottom = || {
+            Box::new(boundary::Condition::new(
+                boundary::Type::BounceBack,
+                geometry::Rectangle::new([1.5, 1.5], [3., 3.]),
+            ))
+        };
+        let top = || {
+            Box::new(boundary::Condition::new(
+                boundary::Type::BounceBack,
+                geometry::Plane::new((0, -1), (0, 13)),
+            ))
+        };
+
+        let mut s_d: Solver<_, _> = Solver::new(dense, physics);
+        let mut s_s: Solver<_, _> = Solver::new(sparse, physics);
+        s_d.add_condition(bottom());
+        s_d.add_condition(top());
+        s_s.add_condition(bottom());
+        s_s.add_condition(top());
+        s_d.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s_s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s_d.run(500, 0);
+        s_s.run(500, 0);
+
+        for c in sparse.ids() {
+            let x = sparse.x(c);
+            if wall(x) {
+                continue;
+            }
+            for n in D2Q9::all() {
+                assert_eq!(s_s.f(c, n), s_d.f(dense.idx(x), n));
+            }
+        }
+    }
+
+    /// Flow past a cylinder in a channel with a velocity inlet and a
+    /// convective outlet using the propagation scheme `propagation`, the
+    /// memory layout `L`, and the floating-point type `T`.
+    fn cylinder_channel<G: Grid, L: Layout, T: Float>(
+        grid: G,
+        propagation: Propagation,
+    ) -> Solver<NavierStokes<D2Q9, TwoRelaxationTime>, G, L, T> {
+        use force;
+        let (l_x, l_y) = (40, 20);
+        let col = TwoRelaxationTime::with_magic_parameter(1.6, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+        let u_0 = 0.05;
+
+        let mut s = Solver::new(grid, physics).with_propagation(propagation);
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::InterpolatedBounceBack(
+                boundary::Interpolation::Bouzidi,
+            ),
+            geometry::Circle::new(l_x, l_y),
+        )));
+        s.add_condition(Box::new(
+            boundary::Condition::with_velocity(
+                geometry::Plane::new((1, 0), (0, 0)),
+                move |_, _| [u_0, 0., 0.],
+            ),
+        ));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::Convective(u_0),
+            geometry::Plane::new((-1, 0), (l_x - 1, 0)),
+        )));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::BounceBack,
+            geometry::Plane::new((0, -1), (0, l_y - 1)),
+        )));
+        let reference = force::Reference::new(1., u_0, 5.);
+        s.forces = Some(force::Monitor::new([12., 10., 0.], reference));
+        s.initialize(|_| {
+            D2Q9::equilibrium(T::from_f64(1.), [T::default(); 3])
+        });
+        s.run(300, 0);
+        s
+    }
+
+    /// The in-place swap propagation reproduces the two-array propagation
+    /// bit by bit.
+    #[test]
+    fn swap_propagation() {
+        let grid = grid::StructuredRectangular::new(40, 20)
+            .with_periodic([false, false]);
+        let s_2 = cylinder_channel::<_, AoS, f64>(grid, Propagation::TwoArrays);
+        let s_1 = cylinder_channel::<_, AoS, f64>(grid, Propagation::Swap);
+        for c in grid.ids() {
+            for n in D2Q9::all() {
+                assert_eq!(s_1.f(c, n), s_2.f(c, n));
+            }
+        }
+        let loads = s_1.forces.as_ref().unwrap().loads();
+        assert_eq!(loads, s_2.forces.as_ref().unwrap().loads());
+        assert_eq!(s_1.loads([12., 10., 0.]), loads);
+        assert!(loads[0].force[0] > 0.);
+    }
+
+    /// The fused stream-collide kernel reproduces the separate passes bit
+    /// by bit.
+    #[test]
+    fn fused_propagation() {
+        let grid = grid::StructuredRectangular::new(40, 20)
+            .with_periodic([false, false]);
+        let s_3 = cylinder_channel::<_, AoS, f64>(grid, Propagation::TwoArrays);
+        let s_1 = cylinder_channel::<_, AoS, f64>(grid, Propagation::Fused);
+        for c in grid.ids() {
+            for n in D2Q9::all() {
+                assert_eq!(s_1.f(c, n), s_3.f(c, n));
+            }
+        }
+        let loads = s_1.forces.as_ref().unwrap().loads();
+        assert_eq!(loads, s_3.forces.as_ref().unwrap().loads());
+        assert_eq!(s_1.loads([12., 10., 0.]), loads);
+    }
+
+    /// All memory layouts reproduce the array-of-structures layout bit by
+    /// bit.
+    #[test]
+    fn layouts() {
+        fn same_as_aos<L: Layout>(propagation: Propagation) {
+            let grid = grid::StructuredRectangular::new(40, 20)
+                .with_periodic([false, false]);
+            let s = cylinder_channel::<_, AoS, f64>(grid, propagation);
+            let s_l = cylinder_channel::<_, L, f64>(grid, propagation);
+            for c in grid.ids() {
+                for n in D2Q9::all() {
+                    assert_eq!(s_l.f(c, n), s.f(c, n));
+                }
+            }
+            let loads = s_l.forces.as_ref().unwrap().loads();
+            assert_eq!(loads, s.forces.as_ref().unwrap().loads());
+            // converting the layout keeps the populations:
+            let s = s.with_layout::<L>();
+            for c in grid.ids() {
+                for n in D2Q9::all() {
+                    assert_eq!(s.f(c, n), s_l.f(c, n));
+                }
+            }
+        }
+        same_as_aos::<layout::SoA>(Propagation::TwoArrays);
+        same_as_aos::<layout::SoA>(Propagation::Swap);
+        same_as_aos::<layout::AoSoA8>(Propagation::Fused);
+    }
+
+    /// Single precision populations agree with double precision ones up to
+    /// their rounding errors.
+    #[test]
+    fn single_precision() {
+        let grid = grid::StructuredRectangular::new(40, 20)
+            .with_periodic([false, false]);
+        for &propagation in &[Propagation::TwoArrays, Propagation::Fused] {
+            let s = cylinder_channel::<_, AoS, f64>(grid, propagation);
+            let s_f = cylinder_channel::<_, AoS, f32>(grid, propagation);
+            let mut error: f64 = 0.;
+            for c in grid.ids() {
+                for n in D2Q9::all() {
+                    let v = s.f(c, n);
+                    error = error.max((f64::from(s_f.f(c, n)) - v).abs() / v);
+                }
+            }
+            assert!(error > 0. && error < 1e-5);
+            let f = s.forces.as_ref().unwrap().loads()[0].force;
+            let f_f = s_f.forces.as_ref().unwrap().loads()[0].force;
+            for d in 0..2 {
+                assert!((f_f[d] - f[d]).abs() < 1e-4 * f[0]);
+            }
+            // converting the precision rounds the populations:
+            let s_c = cylinder_channel::<_, AoS, f64>(grid, propagation)
+                .with_float::<f32>();
+            for c in grid.ids() {
+                for n in D2Q9::all() {
+                    assert_eq!(s_c.f(c, n), s.f(c, n) as f32);
+                }
+            }
+        }
+    }
+
+    /// The ends of a non-periodic axis without boundary conditions are
+    /// half-way bounce-back walls just outside the grid.
+    #[test]
+    fn non_periodic_poiseuille() {
+        let grid = grid::StructuredRectangular::new(3, 10)
+            .with_periodic([true, false]);
+        let (physics, mut s) = channel(grid, &[]);
+        s.run(4000, 0);
+
+        let nu = D2Q9::c_squ() * (1. / OMEGA - 0.5);
+        let (y_0, y_1) = (-0.5, grid.y as f64 - 0.5);
+        let u_max = G / (8. * nu) * (y_1 - y_0).powi(2);
+        for c in grid.ids() {
+            let x = grid.x(c);
+            let y = x.1 as f64;
+            let u = physics.post_collision_velocities(false, x, 0, |n| {
+                s.f(c, n)
+            });
+            let expected = G / (2. * nu) * (y - y_0) * (y_1 - y);
+            assert!((u[0] - expected).abs() < 1e-6 * u_max);
+            assert!(u[1].abs() < 1e-6 * u_max);
+        }
+    }
+
+    /// Maximum error relative to the peak velocity of Poiseuille flow
+    /// between walls located off the lattice links at `y = 2.3` and
+    /// `y = 11.2`.
+    fn off_lattice_poiseuille(wall_type: boundary::Type) -> f64 {
+        let grid = grid::StructuredRectangular::new(3, 16);
+        let (y_0, y_1) = (2.3, 11.2);
+        let g = 1e-6;
+        let col = TwoRelaxationTime::with_magic_parameter(1.1, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col)
+            .with_force(move |_, _| [g, 0., 0.]);
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        let wall = |y_lo: f64, y_hi: f64| {
+            Box::new(boundary::Condition::new(
+                wall_type,
+                geometry::Rectangle::new(
+                    [1., 0.5 * (y_lo + y_hi)],
+                    [10., y_hi - y_lo],
+                ),
+            ))
+        };
+        s.add_condition(wall(-1., y_0));
+        s.add_condition(wall(y_1, grid.y as f64));
+        s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s.run(4000, 0);
+
+        let nu = D2Q9::c_squ() * (1. / col.omega_plus - 0.5);
+        let u_max = g / (8. * nu) * (y_1 - y_0).powi(2);
+        let mut error: f64 = 0.;
+        for c in grid.ids() {
+            let x = grid.x(c);
+            let y = x.1 as f64;
+            if y < y_0 || y > y_1 {
+                continue;
+            }
+            let u = physics.post_collision_velocities(false, x, 0, |n| {
+                s.f(c, n)
+            });
+            let expected = g / (2. * nu) * (y - y_0) * (y_1 - y);
+            error = error.max((u[0] - expected).abs() / u_max);
+            assert!(u[1].abs() < 1e-6 * u_max);
+        }
+        error
+    }
+
+    /// Interpolated bounce-back resolves the wall position within the
+    /// cells, staircase bounce-back does not.
+    #[test]
+    fn interpolated_bounce_back() {
+        use boundary::{Interpolation, Type};
+        let staircase = off_lattice_poiseuille(Type::BounceBack);
+        for &interpolation in &[Interpolation::Bouzidi, Interpolation::Yu] {
+            let error = off_lattice_poiseuille(
+                Type::InterpolatedBounceBack(interpolation),
+            );
+            assert!(error < 4e-2);
+            assert!(error < 0.3 * staircase);
+        }
+    }
+
+    /// Uniform flow started impulsively at a velocity inlet settles and
+    /// leaves through the outflow boundary `outlet`.
+    fn outflow(outlet: boundary::Type) {
+        let grid = grid::StructuredRectangular::new(30, 4);
+        let col = TwoRelaxationTime::with_magic_parameter(1.2, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+        let u_0 = 0.02;
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        s.add_condition(Box::new(
+            boundary::Condition::with_velocity(
+                geometry::Plane::new((1, 0), (0, 0)),
+                move |_, _| [u_0, 0., 0.],
+            ),
+        ));
+        s.add_condition(Box::new(boundary::Condition::new(
+            outlet,
+            geometry::Plane::new((-1, 0), (grid.x - 1, 0)),
+        )));
+        s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s.run(1500, 0);
+
+        let rho_0 = D2Q9::density(|n| s.f(grid::Idx(0), n));
+        assert!((rho_0 - 1.).abs() < 0.1);
+        for c in grid.ids() {
+            let u = D2Q9::velocities(|n| s.f(c, n));
+            assert!((u[0] - u_0).abs() < 1e-6 * u_0);
+            assert!(u[1].abs() < 1e-6 * u_0);
+            let rho = D2Q9::density(|n| s.f(c, n));
+            assert!((rho - rho_0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn zero_gradient_outflow() {
+        outflow(boundary::Type::ZeroGradient);
+    }
+
+    #[test]
+    fn convective_outflow() {
+        outflow(boundary::Type::Convective(0.02));
+    }
+
+    /// Largest density deviation left in a channel closed by the outlets
+    /// `outlet` once a right-moving acoustic pulse started in its middle
+    /// reached the ends of the non-periodic x axis, relative to the
+    /// amplitude of the pulse.
+    fn pulse_reflection(outlet: Option<boundary::Type>) -> f64 {
+        let grid = grid::StructuredRectangular::new(120, 1)
+            .with_periodic([false, true]);
+        let col = TwoRelaxationTime::with_magic_parameter(1.9, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+        let (a, x_0, sigma) = (1e-3, 60., 6.);
+        let c_s = D2Q9::c_squ().sqrt();
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        if let Some(outlet) = outlet {
+            s.add_condition(Box::new(boundary::Condition::new(
+                outlet,
+                geometry::Plane::new((1, 0), (0, 0)),
+            )));
+            s.add_condition(Box::new(boundary::Condition::new(
+                outlet,
+                geometry::Plane::new((-1, 0), (grid.x - 1, 0)),
+            )));
+        }
+        s.initialize(|x| {
+            let d = (x.0 as f64 - x_0) / sigma;
+            let rho = a * (-0.5 * d * d).exp();
+            D2Q9::equilibrium(1. + rho, [c_s * rho, 0., 0.])
+        });
+        s.run(250, 0);
+
+        let mut deviation: f64 = 0.;
+        for c in grid.ids() {
+            let rho = D2Q9::density(|n| s.f(c, n));
+            deviation = deviation.max((rho - 1.).abs() / a);
+        }
+        deviation
+    }
+
+    /// An acoustic pulse leaves through convective outlets moving with the
+    /// speed of sound almost without reflection, while the closed ends of
+    /// the axis reflect it.
+    #[test]
+    fn pulse_outflow() {
+        use boundary::Type;
+        let closed = pulse_reflection(None);
+        assert!(closed > 0.8);
+        let zero_gradient = pulse_reflection(Some(Type::ZeroGradient));
+        assert!(zero_gradient < 0.5 * closed);
+        let c_s = D2Q9::c_squ().sqrt();
+        let convective = pulse_reflection(Some(Type::Convective(c_s)));
+        assert!(convective < 1e-2);
+    }
+
+    /// Body force driven flow between a bounce-back wall and a specular
+    /// symmetry plane is one half of the Poiseuille flow of a channel twice
+    /// as wide.
+    #[test]
+    fn symmetry_plane() {
+        use boundary::Type::{BounceBack, Specular};
+        let grid = grid::StructuredRectangular::new(3, 8);
+        let (physics, mut s) = channel(grid, &[
+            (BounceBack, (0, 1), (0, 0)),
+            (Specular, (0, -1), (0, grid.y - 1)),
+        ]);
+        s.run(4000, 0);
+
+        let nu = D2Q9::c_squ() * (1. / OMEGA - 0.5);
+        // walls of the full channel:
+        let (y_0, y_1) = (0.5, 2. * (grid.y as f64 - 1.5) - 0.5);
+        let u_max = G / (8. * nu) * (y_1 - y_0).powi(2);
+        for c in grid.ids() {
+            let x = grid.x(c);
+            if x.1 == 0 || x.1 == grid.y - 1 {
+                continue;
+            }
+            let y = x.1 as f64;
+            let u = physics.post_collision_velocities(false, x, 0, |n| {
+                s.f(c, n)
+            });
+            let expected = G / (2. * nu) * (y - y_0) * (y_1 - y);
+            assert!((u[0] - expected).abs() < 1e-6 * u_max);
+            assert!(u[1].abs() < 1e-6 * u_max);
+        }
+    }
+
+    /// Couette flow between a resting and a moving wall has a linear
+    /// velocity profile.
+    #[test]
+    fn couette() {
+        let grid = grid::StructuredRectangular::new(3, 12);
+        let u_w = 0.02;
+        let col = TwoRelaxationTime::with_magic_parameter(0.8, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::BounceBack,
+            geometry::Plane::new((0, 1), (0, 0)),
+        )));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::MovingWall([u_w, 0., 0.]),
+            geometry::Plane::new((0, -1), (0, grid.y - 1)),
+        )));
+        s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s.run(3000, 0);
+
+        let (y_0, y_1) = (0.5, grid.y as f64 - 1.5);
+        for c in grid.ids() {
+            let x = grid.x(c);
+            if x.1 == 0 || x.1 == grid.y - 1 {
+                continue;
+            }
+            let u = D2Q9::velocities(|n| s.f(c, n));
+            let expected = u_w * (x.1 as f64 - y_0) / (y_1 - y_0);
+            assert!((u[0] - expected).abs() < 1e-6 * u_w);
+            assert!(u[1].abs() < 1e-6 * u_w);
+        }
+    }
+
+    /// Channel flow between bounce-back walls with a parabolic Zou-He
+    /// velocity inlet and a Zou-He pressure outlet.
+    #[test]
+    fn zou_he_channel() {
+        let grid = grid::StructuredRectangular::new(40, 12);
+        let col = TwoRelaxationTime::with_magic_parameter(1.2, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+        let (y_0, y_1) = (0.5, grid.y as f64 - 1.5);
+        let u_max = 0.01;
+        let profile = move |y: f64| {
+            4. * u_max * (y - y_0) * (y_1 - y) / ((y_1 - y_0) * (y_1 - y_0))
+        };
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        s.add_condition(Box::new(
+            boundary::Condition::with_velocity(
+                geometry::Plane::new((1, 0), (0, 0)),
+                move |x: grid::X, _| [profile(x.1 as f64), 0., 0.],
+            ),
+        ));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::Pressure(1.),
+            geometry::Plane::new((-1, 0), (grid.x - 1, 0)),
+        )));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::BounceBack,
+            geometry::Plane::new((0, 1), (0, 0)),
+        )));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::BounceBack,
+            geometry::Plane::new((0, -1), (0, grid.y - 1)),
+        )));
+        s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s.run(2500, 0);
+
+        for c in grid.ids() {
+            let x = grid.x(c);
+            if x.1 == 0 || x.1 == grid.y - 1 {
+                continue;
+            }
+            let u = D2Q9::velocities(|n| s.f(c, n));
+            assert!((u[0] - profile(x.1 as f64)).abs() < 2e-2 * u_max);
+            assert!(u[1].abs() < 1e-2 * u_max);
+            if x.0 == grid.x - 1 {
+                assert!((D2Q9::density(|n| s.f(c, n)) - 1.).abs() < 1e-12);
+            }
+        }
+
+        // pressure drop of Poiseuille flow away from the inlet and outlet:
+        let nu = D2Q9::c_squ() * (1. / col.omega_plus - 0.5);
+        let rho = |x| D2Q9::density(|n| s.f(grid.idx(grid::X(x, 6, 0)), n));
+        let dp_dx = D2Q9::c_squ() * (rho(36) - rho(4)) / 32.;
+        let expected = -8. * nu * u_max / ((y_1 - y_0) * (y_1 - y_0));
+        assert!((dp_dx - expected).abs() < 2e-2 * expected.abs());
+    }
+
+    /// Channel flow with walls, a parabolic inlet, and an outlet all given
+    /// by non-equilibrium extrapolation boundaries, which also handle the
+    /// corners.
+    #[test]
+    fn extrapolated_channel() {
+        let grid = grid::StructuredRectangular::new(40, 11);
+        let col = TwoRelaxationTime::with_magic_parameter(1.2, 3. / 16.);
+        let physics = NavierStokes::<D2Q9, _>::new(1., 0., col);
+        let (y_0, y_1) = (0., grid.y as f64 - 1.);
+        let u_max = 0.01;
+        let profile = move |y: f64| {
+            4. * u_max * (y - y_0) * (y_1 - y) / ((y_1 - y_0) * (y_1 - y_0))
+        };
+
+        let mut s: Solver<_, _> = Solver::new(grid, physics);
+        s.add_condition(Box::new(
+            boundary::Condition::new_with_velocity(
+                boundary::Type::ExtrapolatedVelocity,
+                geometry::Plane::new((1, 0), (0, 0)),
+                move |x: grid::X, _| [profile(x.1 as f64), 0., 0.],
+            ),
+        ));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::ExtrapolatedPressure(1.),
+            geometry::Plane::new((-1, 0), (grid.x - 1, 0)),
+        )));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::ExtrapolatedVelocity,
+            geometry::Plane::new((0, 1), (0, 0)),
+        )));
+        s.add_condition(Box::new(boundary::Condition::new(
+            boundary::Type::ExtrapolatedVelocity,
+            geometry::Plane::new((0, -1), (0, grid.y - 1)),
+        )));
+        s.initialize(|_| D2Q9::equilibrium(1., [0., 0., 0.]));
+        s.run(2500, 0);
+
+        for c in grid.ids() {
+            let x = grid.x(c);
+            let u = D2Q9::velocities(|n| s.f(c, n));
+            assert!((u[0] - profile(x.1 as f64)).abs() < 2e-2 * u_max);
+            assert!(u[1].abs() < 1e-2 * u_max);
+        }
+
+        // pressure drop of Poiseuille flow away from the inlet and outlet:
+        let nu = D2Q9::c_squ() * (1. / col.omega_plus - 0.5);
+        let rho = |x| D2Q9::density(|n| s.f(grid.idx(grid::X(x, 5, 0)), n));
+        let dp_dx = D2Q9::c_squ() * (rho(36) - rho(4)) / 32.;
+        let expected = -8. * nu * u_max / ((y_1 - y_0) * (y_1 - y_0));
+        assert!((dp_dx - expected).abs() < 2e-2 * expected.abs());
+    }
+}
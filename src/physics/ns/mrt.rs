@@ -0,0 +1,157 @@
+//! Multiple relaxation time (MRT) collision
+use Float;
+use distribution::D2Q9;
+use traits::Distribution;
+use super::ViscousCollision;
+
+/// Moment matrix of the D2Q9 lattice (Lallemand and Luo, 2000).
+///
+/// The rows are the moments `rho`, `e`, `epsilon`, `j_x`, `q_x`, `j_y`,
+/// `q_y`, `p_xx`, `p_xy`; the columns follow the `D2Q9` ordering.
+const M: [[f64; 9]; 9] = [
+    [1., 1., 1., 1., 1., 1., 1., 1., 1.],
+    [-4., -1., -1., -1., -1., 2., 2., 2., 2.],
+    [4., -2., -2., -2., -2., 1., 1., 1., 1.],
+    [0., 1., 0., -1., 0., 1., -1., -1., 1.],
+    [0., -2., 0., 2., 0., 1., -1., -1., 1.],
+    [0., 0., 1., 0., -1., 1., 1., -1., -1.],
+    [0., 0., -2., 0., 2., 1., 1., -1., -1.],
+    [0., 1., -1., 1., -1., 0., 0., 0., 0.],
+    [0., 0., 0., 0., 0., 1., -1., 1., -1.],
+];
+
+/// Squared norms of the rows of `M`: since the rows are orthogonal,
+/// `M^-1 = M^T diag(1 / M_NORM)`.
+const M_NORM: [f64; 9] = [9., 36., 36., 6., 12., 6., 12., 4., 4.];
+
+/// Multiple relaxation time (MRT) algorithm for the D2Q9 lattice.
+///
+/// Setting all rates to the same `omega` recovers `SingleRelaxationTime`.
+#[derive(Copy, Clone)]
+pub struct MultipleRelaxationTime {
+    /// Rate of the energy moment `e` (sets the bulk viscosity)
+    pub bulk: f64,
+    /// Rate of the stress moments `p_xx` and `p_xy` (sets the kinematic
+    /// viscosity like the `omega` of `SingleRelaxationTime`)
+    pub shear: f64,
+    /// Rate of the energy-square moment `epsilon`
+    pub energy: f64,
+    /// Rate of the energy-flux moments `q_x` and `q_y`
+    pub ghost: f64,
+}
+
+impl ViscousCollision for MultipleRelaxationTime {
+    fn omega(&self) -> f64 {
+        self.shear
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self {
+            shear: omega,
+            ..*self
+        }
+    }
+}
+
+impl ::Collision<D2Q9> for MultipleRelaxationTime {
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> [T; 9]
+    where
+        T: Float,
+        IH: Fn(&H, D2Q9) -> T,
+    {
+        let t = T::from_f64;
+        let mut f = [T::default(); 9];
+        for n in D2Q9::all() {
+            f[n.value()] = idx_h(f_hlp, n);
+        }
+
+        // transform to moment space:
+        let mut m = [T::default(); 9];
+        for (m, row) in m.iter_mut().zip(M.iter()) {
+            for (&f, &c) in f.iter().zip(row.iter()) {
+                *m += t(c) * f;
+            }
+        }
+
+        // equilibrium moments:
+        let (rho, j_x, j_y) = (m[0], m[3], m[5]);
+        let j_squ = (j_x * j_x + j_y * j_y) / rho;
+        let m_equ = [
+            rho,
+            t(-2.) * rho + t(3.) * j_squ,
+            rho - t(3.) * j_squ,
+            j_x,
+            -j_x,
+            j_y,
+            -j_y,
+            (j_x * j_x - j_y * j_y) / rho,
+            j_x * j_y / rho,
+        ];
+
+        // relaxation step (the conserved moments have zero rate):
+        let s = [
+            0.,
+            self.bulk,
+            self.energy,
+            0.,
+            self.ghost,
+            0.,
+            self.ghost,
+            self.shear,
+            self.shear,
+        ];
+        let mut dm = [T::default(); 9];
+        for k in 0..9 {
+            dm[k] = t(s[k]) * (m[k] - m_equ[k]) / t(M_NORM[k]);
+        }
+
+        // transform back to population space:
+        for (i, f) in f.iter_mut().enumerate() {
+            for k in 0..9 {
+                *f -= t(M[k][i]) * dm[k];
+            }
+        }
+        f
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use physics::ns::Distribution as NsDistribution;
+    use physics::ns::{assert_recovers_srt, populations};
+    use Collision;
+
+    #[test]
+    fn equal_rates_recover_srt() {
+        let omega = 1.7;
+        let mrt = MultipleRelaxationTime {
+            bulk: omega,
+            shear: omega,
+            energy: omega,
+            ghost: omega,
+        };
+        let f = populations::<D2Q9>(1., [0.03, -0.01, 0.], 0.05);
+        assert_recovers_srt::<D2Q9, _>(mrt, omega, &f);
+    }
+
+    #[test]
+    fn conserves_mass_and_momentum() {
+        let mrt = MultipleRelaxationTime {
+            bulk: 1.64,
+            shear: 1.95,
+            energy: 1.54,
+            ghost: 1.9,
+        };
+        let f = populations::<D2Q9>(1., [0.03, -0.01, 0.], 0.05);
+        let r = mrt.collision(&f, |f, n: D2Q9| f[n.value()]);
+        let rho_0 = D2Q9::density(|n| f[n.value()]);
+        let rho = D2Q9::density(|n| r[n.value()]);
+        assert!((rho - rho_0).abs() < 1e-15);
+        let u_0 = D2Q9::velocities(|n| f[n.value()]);
+        let u = D2Q9::velocities(|n| r[n.value()]);
+        for d in 0..2 {
+            assert!((u[d] - u_0[d]).abs() < 1e-15);
+        }
+    }
+}
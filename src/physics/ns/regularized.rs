@@ -0,0 +1,214 @@
+//! Regularized collisions
+use Float;
+use super::{non_equilibrium_stress, Distribution, ViscousCollision};
+
+/// Second-order Hermite polynomial `c_a c_b - c_s^2 delta_ab` of the
+/// direction `n`.
+#[inline(always)]
+fn hermite_2<D: Distribution>(n: D, a: usize, b: usize) -> f64 {
+    let c = n.direction().num_array();
+    let delta = if a == b { 1. } else { 0. };
+    c[a] * c[b] - D::c_squ() * delta
+}
+
+/// Third-order Hermite polynomial
+/// `c_a c_b c_c - c_s^2 (c_a delta_bc + c_b delta_ac + c_c delta_ab)` of
+/// the direction `n`.
+#[inline(always)]
+fn hermite_3<D: Distribution>(n: D, a: usize, b: usize, d: usize) -> f64 {
+    let c = n.direction().num_array();
+    let delta = |i, j| if i == j { 1. } else { 0. };
+    c[a] * c[b] * c[d] -
+        D::c_squ() * (c[a] * delta(b, d) + c[b] * delta(a, d) +
+                          c[d] * delta(a, b))
+}
+
+/// Regularized single relaxation time algorithm.
+///
+/// Before relaxing, the non-equilibrium part of the populations is replaced
+/// by its projection onto the second-order Hermite polynomials, which
+/// filters out the non-hydrodynamic (ghost) content:
+///
+/// `f_i = f_eq_i + (1 - omega) w_i / (2 c_s^4) H_i : Pi_neq`
+#[derive(Copy, Clone)]
+pub struct Regularized {
+    pub omega: f64,
+}
+
+impl ViscousCollision for Regularized {
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self { omega }
+    }
+}
+
+impl<D: Distribution> ::Collision<D> for Regularized {
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let u = D::velocities(&f_h);
+        let t = T::from_f64;
+
+        let mut f = D::Storage::<T>::default();
+        for n in D::all() {
+            f.as_mut()[n.value()] = f_h(n);
+        }
+        let mut r = D::equilibrium(dloc, u);
+        let pi = non_equilibrium_stress::<T, D>(f.as_ref(), r.as_ref());
+
+        // regularized relaxation step:
+        let c_4 = D::c_squ() * D::c_squ();
+        for n in D::all() {
+            let mut h_pi = T::default();
+            for a in 0..3 {
+                for b in 0..3 {
+                    h_pi += t(hermite_2(n, a, b)) * pi[a][b];
+                }
+            }
+            r.as_mut()[n.value()] +=
+                t((1. - self.omega) * n.constant() / (2. * c_4)) * h_pi;
+        }
+        r
+    }
+}
+
+/// Recursive regularized single relaxation time algorithm.
+///
+/// Extends `Regularized` with the third-order Hermite terms of both the
+/// equilibrium, `a_eq_abc = rho u_a u_b u_c`, and the non-equilibrium part,
+/// which is reconstructed recursively from the second-order one:
+///
+/// `a_neq_abc = u_a Pi_neq_bc + u_b Pi_neq_ac + u_c Pi_neq_ab`
+///
+/// On `D3Q15` the third-order polynomials alias each other, so
+/// `Regularized` should be preferred there.
+#[derive(Copy, Clone)]
+pub struct RecursiveRegularized {
+    pub omega: f64,
+}
+
+impl ViscousCollision for RecursiveRegularized {
+    fn omega(&self) -> f64 {
+        self.omega
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self { omega }
+    }
+}
+
+impl<D: Distribution> ::Collision<D> for RecursiveRegularized {
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let u = D::velocities(&f_h);
+        let t = T::from_f64;
+
+        let mut f = D::Storage::<T>::default();
+        for n in D::all() {
+            f.as_mut()[n.value()] = f_h(n);
+        }
+        let mut r = D::equilibrium(dloc, u);
+        let pi = non_equilibrium_stress::<T, D>(f.as_ref(), r.as_ref());
+
+        // third-order equilibrium and non-equilibrium coefficients:
+        let mut a_3 = [[[T::default(); 3]; 3]; 3];
+        for a in 0..3 {
+            for b in 0..3 {
+                for c in 0..3 {
+                    let a_equ = dloc * u[a] * u[b] * u[c];
+                    let a_neq =
+                        u[a] * pi[b][c] + u[b] * pi[a][c] + u[c] * pi[a][b];
+                    a_3[a][b][c] = a_equ + t(1. - self.omega) * a_neq;
+                }
+            }
+        }
+
+        // regularized relaxation step:
+        let c_4 = D::c_squ() * D::c_squ();
+        let c_6 = c_4 * D::c_squ();
+        for n in D::all() {
+            let mut h_pi = T::default();
+            let mut h_a = T::default();
+            for a in 0..3 {
+                for b in 0..3 {
+                    h_pi += t(hermite_2(n, a, b)) * pi[a][b];
+                    for c in 0..3 {
+                        h_a += t(hermite_3(n, a, b, c)) * a_3[a][b][c];
+                    }
+                }
+            }
+            r.as_mut()[n.value()] += t(n.constant()) *
+                (t((1. - self.omega) / (2. * c_4)) * h_pi + h_a / t(6. * c_6));
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::{D2Q9, D3Q19};
+    use Collision;
+
+    /// Checks that `collision` conserves mass and momentum and relaxes the
+    /// non-equilibrium stress by `1 - omega`.
+    fn check<D, C>(col: C, omega: f64)
+    where
+        D: Distribution,
+        C: Collision<D>,
+    {
+        let mut f = D::Storage::<f64>::default();
+        for n in D::all() {
+            let v = n.value();
+            f.as_mut()[v] = n.constant() * (1. + 0.05 * (v % 5) as f64);
+        }
+        let f = f.as_ref();
+        let r_ = col.collision(&f, |f, n: D| f[n.value()]);
+        let r = r_.as_ref();
+
+        let rho_0 = D::density(|n| f[n.value()]);
+        let rho = D::density(|n| r[n.value()]);
+        assert!((rho - rho_0).abs() < 1e-14);
+        let u_0 = D::velocities(|n| f[n.value()]);
+        let u = D::velocities(|n| r[n.value()]);
+        for d in 0..3 {
+            assert!((u[d] - u_0[d]).abs() < 1e-14);
+        }
+
+        let f_equ = D::equilibrium(rho_0, u_0);
+        let pi_0 = non_equilibrium_stress::<f64, D>(f, f_equ.as_ref());
+        let pi = non_equilibrium_stress::<f64, D>(r, f_equ.as_ref());
+        for a in 0..3 {
+            for b in 0..3 {
+                let expected = (1. - omega) * pi_0[a][b];
+                assert!((pi[a][b] - expected).abs() < 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn regularized() {
+        check::<D2Q9, _>(Regularized { omega: 1.7 }, 1.7);
+        check::<D3Q19, _>(Regularized { omega: 1.2 }, 1.2);
+    }
+
+    #[test]
+    fn recursive_regularized() {
+        check::<D2Q9, _>(RecursiveRegularized { omega: 1.7 }, 1.7);
+        check::<D3Q19, _>(RecursiveRegularized { omega: 1.2 }, 1.2);
+    }
+}
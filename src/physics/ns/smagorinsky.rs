@@ -0,0 +1,209 @@
+//! Smagorinsky large-eddy simulation model
+use Float;
+use io::vtk;
+use grid;
+use super::{non_equilibrium_stress, Distribution, ViscousCollision};
+
+/// Smagorinsky sub-grid model.
+///
+/// Adds the eddy viscosity `nu_t = (C_s Delta)^2 |S|` to the viscosity of
+/// the `inner` collision by adjusting its relaxation time per cell. The
+/// strain rate follows locally from the non-equilibrium stress `Pi`, which
+/// gives (in lattice units):
+///
+/// `tau = (tau_0 + sqrt(tau_0^2 + 2 sqrt(2) C_s^2 |Pi| / (rho c_s^4))) / 2`
+///
+/// where `tau_0 = 1 / omega_0` is the relaxation time of `inner` and
+/// `|Pi| = sqrt(Pi : Pi)`.
+#[derive(Copy, Clone)]
+pub struct Smagorinsky<C: ViscousCollision> {
+    pub inner: C,
+    /// Smagorinsky constant `C_s`
+    pub constant: f64,
+}
+
+/// Norm `sqrt(Pi : Pi)` of a stress tensor.
+#[inline(always)]
+fn norm<T: Float>(pi: [[T; 3]; 3]) -> f64 {
+    let mut q = T::default();
+    for a in 0..3 {
+        for b in 0..3 {
+            q += pi[a][b] * pi[a][b];
+        }
+    }
+    q.sqrt().to_f64()
+}
+
+impl<C: ViscousCollision> Smagorinsky<C> {
+    pub fn new(inner: C, constant: f64) -> Self {
+        Self { inner, constant }
+    }
+
+    /// `2 sqrt(2) C_s^2 / (rho c_s^4)`
+    #[inline(always)]
+    fn k<D: Distribution>(&self, rho: f64) -> f64 {
+        2. * (2. as f64).sqrt() * self.constant * self.constant /
+            (rho * D::c_squ() * D::c_squ())
+    }
+
+    /// Effective relaxation time from the norm `q` of the pre-collision
+    /// non-equilibrium stress.
+    #[inline(always)]
+    pub fn tau<D: Distribution>(&self, rho: f64, q: f64) -> f64 {
+        let tau_0 = 1. / self.inner.omega();
+        0.5 * (tau_0 + (tau_0 * tau_0 + self.k::<D>(rho) * q).sqrt())
+    }
+
+    /// Effective relaxation time from the norm `q` of the post-collision
+    /// non-equilibrium stress, which is `|1 - 1 / tau|` times the
+    /// pre-collision one.
+    #[inline(always)]
+    pub fn tau_post_collision<D>(&self, rho: f64, q: f64) -> f64
+    where
+        D: Distribution,
+    {
+        let tau_0 = 1. / self.inner.omega();
+        let kq = self.k::<D>(rho) * q;
+        let d = (1. - tau_0) * (1. - tau_0);
+        if tau_0 < 1. && kq <= d {
+            // tau < 1: 4 (1 - tau) (tau - tau_0) = k q
+            0.5 * ((1. + tau_0) - (d - kq).sqrt())
+        } else {
+            // tau > 1: 4 (tau - 1) (tau - tau_0) = k q
+            0.5 * ((1. + tau_0) + (d + kq).sqrt())
+        }
+    }
+
+    /// Eddy viscosity `c_s^2 (tau - tau_0)` of the post-collision
+    /// populations `f`.
+    #[inline(always)]
+    pub fn eddy_viscosity<T, D, F>(&self, f: F) -> T
+    where
+        T: Float,
+        D: Distribution,
+        F: Fn(D) -> T,
+    {
+        let dloc = D::density(&f);
+        let f_equ = D::equilibrium(dloc, D::velocities(&f));
+        let mut f_ = D::Storage::<T>::default();
+        for n in D::all() {
+            f_.as_mut()[n.value()] = f(n);
+        }
+        let pi = non_equilibrium_stress::<T, D>(f_.as_ref(), f_equ.as_ref());
+        let tau = self.tau_post_collision::<D>(dloc.to_f64(), norm(pi));
+        T::from_f64(D::c_squ() * (tau - 1. / self.inner.omega()))
+    }
+}
+
+impl<D, C> ::Collision<D> for Smagorinsky<C>
+where
+    D: Distribution,
+    C: ::Collision<D> + ViscousCollision,
+{
+    type Counts = C::Counts;
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        self.counted_collision(f_hlp, idx_h, &mut C::Counts::default())
+    }
+
+    #[inline(always)]
+    fn counted_collision<T, H, IH>(
+        &self,
+        f_hlp: &H,
+        idx_h: IH,
+        counts: &mut C::Counts,
+    ) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let (dloc, q) = {
+            let f_h = |n| idx_h(f_hlp, n);
+            let dloc = D::density(&f_h);
+            let f_equ = D::equilibrium(dloc, D::velocities(&f_h));
+            let mut f = D::Storage::<T>::default();
+            for n in D::all() {
+                f.as_mut()[n.value()] = f_h(n);
+            }
+            let pi = non_equilibrium_stress::<T, D>(f.as_ref(), f_equ.as_ref());
+            (dloc.to_f64(), norm(pi))
+        };
+
+        let omega = 1. / self.tau::<D>(dloc, q);
+        self.inner
+            .with_omega(omega)
+            .counted_collision(f_hlp, idx_h, counts)
+    }
+
+    fn statistics(&self, counts: C::Counts) -> Option<String> {
+        self.inner.statistics(counts)
+    }
+
+    fn write<T, O, F>(
+        &self,
+        mut vtk_writer: vtk::CellDataWriter,
+        obst: O,
+        f: F,
+    ) -> vtk::CellDataWriter
+    where
+        T: Float,
+        F: Fn(grid::Idx, D) -> T,
+        O: Fn(grid::Idx) -> bool,
+    {
+        vtk_writer.write_scalar("nu_t", |c| if obst(c) {
+            T::default()
+        } else {
+            self.eddy_viscosity(|n| f(c, n))
+        });
+        self.inner.write(vtk_writer, obst, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D2Q9;
+    use physics::ns::{self, SingleRelaxationTime};
+    use traits::Distribution as LatticeDistribution;
+    use Collision;
+
+    fn populations() -> [f64; 9] {
+        ns::populations::<D2Q9>(1., [0.05, -0.02, 0.], 0.01)
+    }
+
+    #[test]
+    fn zero_constant_recovers_inner() {
+        let srt = SingleRelaxationTime { omega: 1.9 };
+        let les = Smagorinsky::new(srt, 0.);
+        let f = populations();
+        let r_les: [f64; 9] = les.collision(&f, |f, n: D2Q9| f[n.value()]);
+        let r_srt: [f64; 9] = srt.collision(&f, |f, n: D2Q9| f[n.value()]);
+        assert_eq!(r_les, r_srt);
+        let nu_t = les.eddy_viscosity(|n: D2Q9| r_les[n.value()]);
+        assert!(nu_t.abs() < 1e-15);
+    }
+
+    #[test]
+    fn eddy_viscosity() {
+        for &omega in &[1.95, 1.2, 0.8] {
+            let les = Smagorinsky::new(SingleRelaxationTime { omega }, 0.17);
+            let f = populations();
+            let rho = D2Q9::density(|n| f[n.value()]);
+            let u = D2Q9::velocities(|n| f[n.value()]);
+            let f_equ = D2Q9::equilibrium(rho, u);
+            let q = norm(non_equilibrium_stress::<f64, D2Q9>(&f, &f_equ));
+            let tau = les.tau::<D2Q9>(rho, q);
+            assert!(tau > 1. / omega);
+
+            let r: [f64; 9] = les.collision(&f, |f, n: D2Q9| f[n.value()]);
+            let nu_t = les.eddy_viscosity(|n: D2Q9| r[n.value()]);
+            let expected = D2Q9::c_squ() * (tau - 1. / omega);
+            assert!((nu_t - expected).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Explicit SIMD kernels of the single relaxation time collision.
+//!
+//! The kernels collide up to `LANES` cells with portable SIMD vectors of 4
+//! or 8 cells. The widest kernel supported by the CPU is detected once, at
+//! the first collision.
+use std::sync::OnceLock;
+use {Float, Vector, MAX_SIZE};
+use super::Distribution;
+
+/// Width of a SIMD collision kernel
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Width {
+    /// No SIMD kernel: collide one cell at a time
+    Scalar,
+    /// Vectors of 4 cells (AVX for `f64`, SSE for `f32`)
+    X4,
+    /// Vectors of 8 cells (AVX-512 for `f64`, AVX for `f32`)
+    X8,
+}
+
+/// Widest SIMD kernel supported by the CPU
+#[inline(always)]
+pub fn width() -> Width {
+    static WIDTH: OnceLock<Width> = OnceLock::new();
+    *WIDTH.get_or_init(detect_width)
+}
+
+fn detect_width() -> Width {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx512f") {
+            return Width::X8;
+        }
+        if is_x86_feature_detected!("avx") {
+            return Width::X4;
+        }
+    }
+    Width::Scalar
+}
+
+/// Collides the populations `f[n.value()][k]` of the cells `k < len` in
+/// place with the SIMD kernel of width `w`.
+///
+/// Returns `false` if `w` is `Width::Scalar` or wider than `width()`,
+/// without modifying `f`.
+#[inline(always)]
+pub fn collision<T: Float, D: Distribution>(
+    omega: T,
+    f: &mut [::Lanes<T>],
+    len: usize,
+    w: Width,
+) -> bool {
+    debug_assert!(D::size() <= MAX_SIZE);
+    debug_assert!(len <= ::LANES);
+    if w == Width::Scalar || w > width() {
+        return false;
+    }
+    // only reached with a kernel supported by the CPU:
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    match w {
+        Width::X8 => unsafe { collision_avx512::<T, D>(omega, f, len) },
+        _ => unsafe { collision_avx::<T, D>(omega, f, len) },
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    let _ = (omega, f, len);
+    true
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx")]
+unsafe fn collision_avx<T: Float, D: Distribution>(
+    omega: T,
+    f: &mut [::Lanes<T>],
+    len: usize,
+) {
+    kernel::<T, D, T::X4>(omega, f, len);
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx512f")]
+unsafe fn collision_avx512<T: Float, D: Distribution>(
+    omega: T,
+    f: &mut [::Lanes<T>],
+    len: usize,
+) {
+    kernel::<T, D, T::X8>(omega, f, len);
+}
+
+/// Collides the cells `k < len` in vectors `V` of `V::LANES` cells.
+///
+/// The operations are those of the scalar collision, in the same order.
+/// The lanes of the last vector past `len` are not written.
+#[inline(always)]
+fn kernel<T, D, V>(omega: T, f: &mut [::Lanes<T>], len: usize)
+where
+    T: Float,
+    D: Distribution,
+    V: Vector<T>,
+{
+    let n_v = V::LANES;
+    let splat = |v: f64| V::splat(T::from_f64(v));
+    let c_squ = splat(D::c_squ());
+    let one = splat(1.);
+    let omega = V::splat(omega);
+
+    for o in (0..len).step_by(n_v) {
+        // number of lanes of the vector holding cells:
+        let m = n_v.min(len - o);
+        let mut f_v = [splat(0.); MAX_SIZE];
+        for n in D::all() {
+            f_v[n.value()] = V::from_slice(&f[n.value()][o..o + n_v]);
+        }
+
+        // local density and vel components:
+        let mut dloc = splat(0.);
+        for n in D::all() {
+            dloc += f_v[n.value()];
+        }
+        let mut u = [splat(0.); 3];
+        for d in 0..3 {
+            for n in D::all() {
+                let c = splat(n.direction().num_array()[d]);
+                u[d] += c * f_v[n.value()];
+            }
+            u[d] /= dloc;
+        }
+
+        // equilibrium densities:
+        let f0 = splat(2.) * c_squ * c_squ;
+        let f1 = splat(2.) * c_squ;
+        let u_squ = u[0] * u[0] + u[1] * u[1] + u[2] * u[2];
+        let f2 = u_squ / f1;
+
+        // relaxation step:
+        for n in D::all() {
+            let v = n.direction().num_array();
+            let u_n =
+                splat(v[0]) * u[0] + splat(v[1]) * u[1] + splat(v[2]) * u[2];
+            let f3 = splat(n.constant()) * dloc;
+            let n_equ = f3 * (one + u_n / c_squ + u_n * u_n / f0 - f2);
+            let f_n = f_v[n.value()];
+            let r = f_n + omega * (n_equ - f_n);
+            if m == n_v {
+                r.copy_to_slice(&mut f[n.value()][o..o + n_v]);
+            } else {
+                let mut r_ = [T::default(); ::LANES];
+                r.copy_to_slice(&mut r_);
+                f[n.value()][o..o + m].copy_from_slice(&r_[..m]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::{D2Q9, D3Q19};
+    use physics::ns::{self, SingleRelaxationTime};
+    use traits::{scalar_collision_lanes, Lanes, LANES};
+    use traits::Distribution as LatticeDistribution;
+
+    /// Populations of `LANES` cells around the equilibrium with different
+    /// densities and velocities.
+    fn populations<T: Float, D: Distribution>() -> Vec<Lanes<T>> {
+        let mut f = vec![[T::default(); LANES]; D::size()];
+        for k in 0..LANES {
+            let k_ = k as f64;
+            let u = [0.01 * k_, -0.02 + 0.003 * k_, 0.005 * k_];
+            let f_k = ns::populations::<D>(1. + 0.05 * k_, u, 1e-2);
+            for n in D::all() {
+                f[n.value()][k] = T::from_f64(f_k.as_ref()[n.value()]);
+            }
+        }
+        f
+    }
+
+    /// The kernels agree with the scalar collision within the relative
+    /// tolerance `eps`.
+    fn agree<T: Float, D: Distribution>(eps: f64) {
+        let col = SingleRelaxationTime { omega: 1.7 };
+        let omega = T::from_f64(col.omega);
+        let mut f_s = populations::<T, D>();
+        scalar_collision_lanes::<T, D, _>(&col, &mut f_s, LANES, &mut ());
+
+        let mut f_4 = populations::<T, D>();
+        kernel::<T, D, T::X4>(omega, &mut f_4, LANES);
+        let mut f_8 = populations::<T, D>();
+        kernel::<T, D, T::X8>(omega, &mut f_8, LANES);
+        let mut f_w = populations::<T, D>();
+        let simd = collision::<T, D>(omega, &mut f_w, LANES, width());
+        assert_eq!(simd, width() != Width::Scalar);
+        if !simd {
+            f_w = f_s.clone();
+        }
+
+        for n in D::all() {
+            for k in 0..LANES {
+                let v = f_s[n.value()][k];
+                for &f in &[&f_4, &f_8, &f_w] {
+                    let e = (f[n.value()][k] - v).abs();
+                    assert!(e <= T::from_f64(eps) * v.abs());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lanes_past_len_are_not_written() {
+        let omega = 1.7;
+        for len in 1..LANES {
+            let mut f = populations::<f64, D2Q9>();
+            // empty lanes:
+            for n in D2Q9::all() {
+                for k in len..LANES {
+                    f[n.value()][k] = 0.;
+                }
+            }
+            let f_0 = f.clone();
+            let mut f_4 = f.clone();
+            kernel::<f64, D2Q9, <f64 as Float>::X4>(omega, &mut f_4, len);
+            kernel::<f64, D2Q9, <f64 as Float>::X8>(omega, &mut f, len);
+            for n in D2Q9::all() {
+                for k in 0..LANES {
+                    let (v, v_4) = (f[n.value()][k], f_4[n.value()][k]);
+                    assert!(v.is_finite() && v == v_4);
+                    if k >= len {
+                        assert_eq!(v, f_0[n.value()][k]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simd_agrees_with_scalar() {
+        agree::<f64, D2Q9>(1e-15);
+        agree::<f64, D3Q19>(1e-15);
+        agree::<f32, D2Q9>(1e-6);
+        agree::<f32, D3Q19>(1e-6);
+    }
+}
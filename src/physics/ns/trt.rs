@@ -0,0 +1,113 @@
+//! Two relaxation time (TRT) collision
+use Float;
+use super::{Distribution, ViscousCollision};
+
+/// Two relaxation time (TRT) algorithm.
+///
+/// The populations are split into a symmetric part `(f_i + f_-i) / 2`,
+/// relaxed with `omega_plus`, and an anti-symmetric part
+/// `(f_i - f_-i) / 2`, relaxed with `omega_minus`.
+#[derive(Copy, Clone)]
+pub struct TwoRelaxationTime {
+    /// Rate of the symmetric part (sets the kinematic viscosity like the
+    /// `omega` of `SingleRelaxationTime`)
+    pub omega_plus: f64,
+    /// Rate of the anti-symmetric part
+    pub omega_minus: f64,
+}
+
+impl TwoRelaxationTime {
+    /// TRT with symmetric rate `omega` and the anti-symmetric rate given by
+    /// the magic parameter `lambda = (1 / omega_plus - 1 / 2) (1 /
+    /// omega_minus - 1 / 2)`.
+    ///
+    /// With `lambda = 3 / 16` bounce-back walls lie exactly half-way
+    /// between nodes for Poiseuille flow independently of the viscosity;
+    /// `lambda = 1 / 4` gives the best stability.
+    pub fn with_magic_parameter(omega: f64, lambda: f64) -> Self {
+        Self {
+            omega_plus: omega,
+            omega_minus: 1. / (lambda / (1. / omega - 0.5) + 0.5),
+        }
+    }
+
+    /// Magic parameter `lambda` of the relaxation rates
+    pub fn magic_parameter(&self) -> f64 {
+        (1. / self.omega_plus - 0.5) * (1. / self.omega_minus - 0.5)
+    }
+}
+
+/// Changing the rate keeps the magic parameter constant.
+impl ViscousCollision for TwoRelaxationTime {
+    fn omega(&self) -> f64 {
+        self.omega_plus
+    }
+    fn with_omega(&self, omega: f64) -> Self {
+        Self::with_magic_parameter(omega, self.magic_parameter())
+    }
+}
+
+impl<D: Distribution> ::Collision<D> for TwoRelaxationTime {
+    #[inline(always)]
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        // local density and vel components:
+        let f_h = |n| idx_h(f_hlp, n);
+        let dloc = D::density(&f_h);
+        let u = D::velocities(&f_h);
+
+        // equilibrium densities:
+        let n_equ_ = D::equilibrium(dloc, u);
+        let n_equ = n_equ_.as_ref();
+
+        // relaxation step:
+        let t = T::from_f64;
+        let omega_plus = t(self.omega_plus);
+        let omega_minus = t(self.omega_minus);
+        let mut r = D::Storage::<T>::default();
+        for n in D::all() {
+            let (i, o) = (n.value(), n.opposite().value());
+            let (f_i, f_o) = (f_h(n), f_h(n.opposite()));
+            let f_p = t(0.5) * (f_i + f_o);
+            let f_m = t(0.5) * (f_i - f_o);
+            let f_equ_p = t(0.5) * (n_equ[i] + n_equ[o]);
+            let f_equ_m = t(0.5) * (n_equ[i] - n_equ[o]);
+            r.as_mut()[i] = f_i - omega_plus * (f_p - f_equ_p) -
+                omega_minus * (f_m - f_equ_m);
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use distribution::D2Q9;
+    use physics::ns::{assert_recovers_srt, populations};
+
+    #[test]
+    fn equal_rates_recover_srt() {
+        let omega = 1.3;
+        let trt = TwoRelaxationTime {
+            omega_plus: omega,
+            omega_minus: omega,
+        };
+        let f = populations::<D2Q9>(1., [0.03, -0.01, 0.], 0.05);
+        assert_recovers_srt::<D2Q9, _>(trt, omega, &f);
+    }
+
+    #[test]
+    fn magic_parameter() {
+        for &lambda in &[0.25, 3. / 16., 1. / 12.] {
+            for &omega in &[0.6, 1.0, 1.7, 1.95] {
+                let trt =
+                    TwoRelaxationTime::with_magic_parameter(omega, lambda);
+                assert_eq!(trt.omega_plus, omega);
+                assert!((trt.magic_parameter() - lambda).abs() < 1e-12);
+            }
+        }
+    }
+}
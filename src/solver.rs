@@ -1,40 +1,233 @@
 //! Implements the Lattice-Boltzmann solver.
 
-use num;
-use grid;
+use std::marker::PhantomData;
+use Float;
+use grid::{self, Grid};
 use boundary;
+use force;
+use layout::{self, Layout};
 use io::{vtk, Serializable};
 use time;
-use traits::{Distribution, DistributionStorage};
+use traits::{Counts, Distribution, DistributionStorage};
+
+/// Propagation scheme of the populations.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Propagation {
+    /// Streaming from the populations into a second array
+    TwoArrays,
+    /// In-place streaming of a single array ("swap" algorithm, Mattila et
+    /// al. 2007)
+    ///
+    /// The post-collision populations are stored in the slots of their
+    /// opposite directions. The streaming then exchanges the two populations
+    /// crossing each link, which leaves every population in its own slot of
+    /// the cell it streamed into.
+    Swap,
+    /// Pull streaming fused with the collision and the boundary conditions
+    /// into a single pass over two arrays
+    ///
+    /// Each cell pulls its populations from the upstream cells, collides
+    /// them, and applies its boundary conditions, which read the streamed
+    /// populations of their neighbors by pulling them as well. Every cell
+    /// is thus written once per iteration.
+    Fused,
+}
+
+/// Pointer to populations whose threads access disjoint populations.
+#[derive(Copy, Clone)]
+struct SharedPopulations<T>(*mut T);
+unsafe impl<T> Send for SharedPopulations<T> {}
+unsafe impl<T> Sync for SharedPopulations<T> {}
 
 /// Lattice-Boltzmann Solver state
-pub struct Solver<P: ::Physics> {
-    grid: grid::StructuredRectangular,
-    pub bcs: boundary::Handler,
+///
+/// The populations are stored with the memory layout `L` and the
+/// floating-point type `T`.
+pub struct Solver<
+    P: ::Physics,
+    G: Grid = grid::StructuredRectangular,
+    L: Layout = layout::AoS,
+    T: Float = f64,
+> {
+    grid: G,
+    bcs: boundary::Handler,
+    /// Cell flags of the boundary conditions
+    flags: boundary::Flags,
+    /// Evaluation of the loads on the boundary conditions (if any)
+    pub forces: Option<force::Monitor>,
     physics: P,
-    f: Box<[num]>, // Distribution functions
-    f_hlp: Box<[num]>,
+    propagation: Propagation,
+    f: Box<[T]>, // Distribution functions
+    f_hlp: Box<[T]>,
+    /// Populations of the cells of the boundary conditions (if any) of the
+    /// current iteration
+    bc_values: Vec<Option<DistributionStorage<P::Distribution, T>>>,
+    /// Streamed populations of the cells of the boundary conditions of the
+    /// current iteration with `Propagation::Swap`, which overwrites them
+    bc_streamed: Vec<DistributionStorage<P::Distribution, T>>,
+    iteration: usize, // Number of completed iterations
+    /// Counters of the collisions since the last step line
+    counts: P::Counts,
+    layout: PhantomData<L>,
 }
 
-impl<P: ::Physics> Solver<P> {
-    /// Create a new solver from a `grid` and `physics`.
-    pub fn new(grid: grid::StructuredRectangular, physics: P) -> Solver<P> {
+impl<P: ::Physics, G: Grid, L: Layout, T: Float> Solver<P, G, L, T> {
+    /// Create a new solver from a `grid` and `physics` storing the
+    /// populations with the memory layout `L` and the floating-point type
+    /// `T`.
+    ///
+    /// Panics if `L` does not map the populations one-to-one into its
+    /// array, which the parallel loops writing them rely on.
+    pub fn new(grid: G, physics: P) -> Self {
+        assert_eq!(
+            G::dimension(),
+            P::Distribution::dimension(),
+            "the grid and the distribution dimensions do not match"
+        );
+        let (n, q) = (grid.size(), P::Distribution::size());
+        layout::assert_one_to_one::<L>(n, q);
         Solver {
             grid,
             bcs: boundary::Handler::default(),
+            flags: boundary::Flags::fluid(grid.size()),
+            forces: None,
             physics,
-            f: vec![0.; grid.size() * P::Distribution::size()]
-                .into_boxed_slice(),
-            f_hlp: vec![0.; grid.size() * P::Distribution::size()]
-                .into_boxed_slice(),
+            propagation: Propagation::TwoArrays,
+            f: vec![T::default(); L::len(n, q)].into_boxed_slice(),
+            f_hlp: vec![T::default(); L::len(n, q)].into_boxed_slice(),
+            bc_values: Vec::new(),
+            bc_streamed: Vec::new(),
+            iteration: 0,
+            counts: Default::default(),
+            layout: PhantomData,
+        }
+    }
+
+    /// Converts the populations of the solver to the memory layout `L2`.
+    ///
+    /// Panics if `L2` does not map the populations one-to-one into its
+    /// array, which the parallel loops writing them rely on.
+    pub fn with_layout<L2: Layout>(self) -> Solver<P, G, L2, T> {
+        let (n, q) = (self.grid.size(), P::Distribution::size());
+        layout::assert_one_to_one::<L2>(n, q);
+        let relayout = |f: &[T]| -> Box<[T]> {
+            if f.is_empty() {
+                return Default::default();
+            }
+            let mut f_2 = vec![T::default(); L2::len(n, q)];
+            for c in 0..n {
+                for i in 0..q {
+                    f_2[L2::idx(c, i, n, q)] = f[L::idx(c, i, n, q)];
+                }
+            }
+            f_2.into_boxed_slice()
+        };
+        Solver {
+            f: relayout(&self.f),
+            f_hlp: relayout(&self.f_hlp),
+            grid: self.grid,
+            bcs: self.bcs,
+            flags: self.flags,
+            forces: self.forces,
+            physics: self.physics,
+            propagation: self.propagation,
+            bc_values: self.bc_values,
+            bc_streamed: self.bc_streamed,
+            iteration: self.iteration,
+            counts: self.counts,
+            layout: PhantomData,
+        }
+    }
+
+    /// Converts the populations of the solver to the floating-point type
+    /// `T2`.
+    ///
+    /// The boundary condition values of the current iteration are
+    /// discarded.
+    pub fn with_float<T2: Float>(self) -> Solver<P, G, L, T2> {
+        let convert = |f: &[T]| -> Box<[T2]> {
+            f.iter().map(|v| T2::from_f64(v.to_f64())).collect()
+        };
+        Solver {
+            f: convert(&self.f),
+            f_hlp: convert(&self.f_hlp),
+            grid: self.grid,
+            bcs: self.bcs,
+            flags: self.flags,
+            forces: self.forces,
+            physics: self.physics,
+            propagation: self.propagation,
+            bc_values: Vec::new(),
+            bc_streamed: Vec::new(),
+            iteration: self.iteration,
+            counts: self.counts,
+            layout: PhantomData,
+        }
+    }
+
+    /// Solver using the propagation scheme `propagation`.
+    ///
+    /// `Propagation::Swap` does not allocate the second population array.
+    /// Panics if the neighbors of the grid are not symmetric.
+    pub fn with_propagation(mut self, propagation: Propagation) -> Self {
+        if propagation == Propagation::Swap {
+            self.assert_symmetric_links();
+        }
+        let size = L::len(self.grid.size(), P::Distribution::size());
+        let mut f = vec![T::default(); size];
+        for c in self.grid.ids() {
+            for n in P::Distribution::all() {
+                f[self.f_idx(c, n)] = self.f(c, n);
+            }
+        }
+        self.propagation = propagation;
+        for c in self.grid.ids() {
+            for n in P::Distribution::all() {
+                *self.f_mut(c, n) = f[self.f_idx(c, n)];
+            }
         }
+        self.f_hlp = match propagation {
+            Propagation::TwoArrays | Propagation::Fused => {
+                vec![T::default(); size].into_boxed_slice()
+            }
+            Propagation::Swap => Default::default(),
+        };
+        self
+    }
+
+    /// Asserts that every neighbor of the grid has the cell as its neighbor
+    /// in the opposite direction, which the in-place swap of the
+    /// populations of each link relies on.
+    fn assert_symmetric_links(&self) {
+        use rayon::prelude::*;
+        let n_cells = self.grid.size();
+        self.grid.par_ids().for_each(|c| for n in P::Distribution::all() {
+            if let Some(cn) = self.grid.neighbor(c, n) {
+                assert!(
+                    cn.0 < n_cells &&
+                        self.grid.neighbor(cn, n.opposite()) == Some(c),
+                    "the neighbors of the grid are not symmetric"
+                );
+            }
+        });
+    }
+
+    /// Adds the boundary condition `bc` and updates the cell flags.
+    pub fn add_condition(&mut self, bc: Box<dyn boundary::AnyCondition>) {
+        self.bcs.push(bc);
+        self.flags = self.bcs.flags(&self.grid);
+    }
+
+    /// Boundary conditions
+    pub fn conditions(&self) -> &boundary::Handler {
+        &self.bcs
     }
 
     /// Initialize distributions functions using `initial_distributions(x)`.
     pub fn initialize<F>(&mut self, initial_distributions: F)
     where
         F: Fn(grid::X)
-            -> DistributionStorage<P::Distribution>,
+            -> DistributionStorage<P::Distribution, T>,
     {
         for c in self.grid.ids() {
             let fs = initial_distributions(self.grid.x(c));
@@ -45,82 +238,612 @@ impl<P: ::Physics> Solver<P> {
     }
 
     /// Index of the distribution function `i` of the cell `c`
-    fn f_idx(c: grid::Idx, i: P::Distribution) -> usize {
-        c.0 * P::Distribution::size() + i.value()
+    #[inline(always)]
+    fn f_idx(&self, c: grid::Idx, i: P::Distribution) -> usize {
+        L::idx(c.0, i.value(), self.grid.size(), P::Distribution::size())
+    }
+
+    /// Distribution functions of the cell `c` in `f`
+    #[inline(always)]
+    fn load(
+        &self,
+        f: &[T],
+        c: grid::Idx,
+    ) -> DistributionStorage<P::Distribution, T> {
+        let mut s = DistributionStorage::<P::Distribution, T>::default();
+        for n in P::Distribution::all() {
+            s.as_mut()[n.value()] = f[self.f_idx(c, n)];
+        }
+        s
+    }
+
+    /// Writes the distribution functions `w(c)` (if any) of all cells `c`
+    /// into `f` in parallel
+    fn par_store<W>(&self, f: &mut [T], w: W)
+    where
+        W: Fn(grid::Idx) -> Option<DistributionStorage<P::Distribution, T>>
+            + Sync,
+    {
+        use rayon::prelude::*;
+        assert_eq!(f.len(), L::len(self.grid.size(), P::Distribution::size()));
+        let p = SharedPopulations(f.as_mut_ptr());
+        self.grid.par_ids().for_each(|c| if let Some(s) = w(c) {
+            for n in P::Distribution::all() {
+                // each cell only writes its own populations:
+                unsafe {
+                    *p.0.add(self.f_idx(c, n)) = s.as_ref()[n.value()];
+                }
+            }
+        });
+    }
+
+    /// Replaces the distribution functions `f_c` of all cells `c` in `f`
+    /// with `w(c, f_c)` (if any) in parallel
+    fn par_update<W>(&self, f: &mut [T], w: W)
+    where
+        W: Fn(grid::Idx, &DistributionStorage<P::Distribution, T>)
+            -> Option<DistributionStorage<P::Distribution, T>>
+            + Sync,
+    {
+        use rayon::prelude::*;
+        assert_eq!(f.len(), L::len(self.grid.size(), P::Distribution::size()));
+        let p = SharedPopulations(f.as_mut_ptr());
+        self.grid.par_ids().for_each(|c| {
+            // each cell only accesses its own populations:
+            let mut f_c = DistributionStorage::<P::Distribution, T>::default();
+            for n in P::Distribution::all() {
+                f_c.as_mut()[n.value()] = unsafe { *p.0.add(self.f_idx(c, n)) };
+            }
+            if let Some(s) = w(c, &f_c) {
+                for n in P::Distribution::all() {
+                    unsafe {
+                        *p.0.add(self.f_idx(c, n)) = s.as_ref()[n.value()];
+                    }
+                }
+            }
+        });
+    }
+
+    /// Index of the distribution function `i` of the cell `c` in between
+    /// iterations
+    fn f_slot(&self, c: grid::Idx, i: P::Distribution) -> usize {
+        match self.propagation {
+            Propagation::TwoArrays | Propagation::Fused => self.f_idx(c, i),
+            Propagation::Swap => self.f_idx(c, i.opposite()),
+        }
+    }
+
+    /// Value of the distribution function `i` of the cell `c`
+    pub fn f(&self, c: grid::Idx, i: P::Distribution) -> T {
+        *self.f_ref(c, i)
     }
 
     /// Mutable reference to the distribution function `i` of the cell `c`
-    fn f_mut(&mut self, c: grid::Idx, i: P::Distribution) -> &mut num {
-        &mut self.f[Self::f_idx(c, i)]
+    fn f_mut(&mut self, c: grid::Idx, i: P::Distribution) -> &mut T {
+        let idx = self.f_slot(c, i);
+        &mut self.f[idx]
     }
 
     /// Reference to the distribution function `i` of the cell `c`
-    fn f_ref(&self, c: grid::Idx, i: P::Distribution) -> &num {
-        &self.f[Self::f_idx(c, i)]
+    fn f_ref(&self, c: grid::Idx, i: P::Distribution) -> &T {
+        &self.f[self.f_slot(c, i)]
+    }
+
+    /// Writes the distribution functions `w(c, r_c)` (if any) of all cells
+    /// `c` into `f` in parallel, where `r_c` are the distribution functions
+    /// `f_c(c, f)` collided in blocks of `LANES` cells.
+    ///
+    /// `f_c(c, f)` may read the distribution functions of `c` from `f`. The
+    /// distribution functions of solid boundary cells are not collided.
+    ///
+    /// Returns the counters of the collisions.
+    fn par_collide<FC, W>(&self, f: &mut [T], f_c: FC, w: W) -> P::Counts
+    where
+        FC: Fn(grid::Idx, *const T) -> DistributionStorage<P::Distribution, T>
+            + Sync,
+        W: Fn(grid::Idx, DistributionStorage<P::Distribution, T>)
+            -> Option<DistributionStorage<P::Distribution, T>>
+            + Sync,
+    {
+        use rayon::prelude::*;
+        use {Lanes, LANES, MAX_SIZE};
+        assert_eq!(f.len(), L::len(self.grid.size(), P::Distribution::size()));
+        let p = SharedPopulations(f.as_mut_ptr());
+        // each cell only accesses its own populations:
+        let store = |c, r| if let Some(s) = w(c, r) {
+            for n in P::Distribution::all() {
+                unsafe {
+                    *p.0.add(self.f_idx(c, n)) = s.as_ref()[n.value()];
+                }
+            }
+        };
+        let n_cells = self.grid.size();
+        let n_blocks = (n_cells + LANES - 1) / LANES;
+        // the populations of a block of the largest lattice:
+        let f_b: [Lanes<T>; MAX_SIZE] = [[T::default(); LANES]; MAX_SIZE];
+        let counts = (0..n_blocks).into_par_iter().map_with(f_b, |f_b, b| {
+            let f_b = &mut f_b[..P::Distribution::size()];
+            let mut cells = [grid::Idx(0); LANES];
+            let mut x = [grid::X(0, 0, 0); LANES];
+            let mut len = 0;
+            for c in b * LANES..n_cells.min((b + 1) * LANES) {
+                let c = grid::Idx(c);
+                let s = f_c(c, p.0);
+                if self.solid_boundary(c) {
+                    store(c, s);
+                    continue;
+                }
+                for n in P::Distribution::all() {
+                    f_b[n.value()][len] = s.as_ref()[n.value()];
+                }
+                cells[len] = c;
+                x[len] = self.grid.x(c);
+                len += 1;
+            }
+            let mut counts = P::Counts::default();
+            self.physics.collision_lanes(
+                f_b,
+                &x[..len],
+                self.iteration,
+                &mut counts,
+            );
+            for (k, &c) in cells[..len].iter().enumerate() {
+                let mut r =
+                    DistributionStorage::<P::Distribution, T>::default();
+                for n in P::Distribution::all() {
+                    r.as_mut()[n.value()] = f_b[n.value()][k];
+                }
+                store(c, r);
+            }
+            counts
+        });
+        counts.reduce(P::Counts::default, |mut a, b| {
+            a.merge(b);
+            a
+        })
     }
 
     /// Is the cell `c` par of a solid boundary?
     fn solid_boundary(&self, c: grid::Idx) -> bool {
-        self.bcs.solid_boundary(self.grid.x(c))
+        self.flags.flag(c).solid()
     }
 
     /// Streaming step
     fn streaming(&mut self) {
-        use rayon::prelude::*;
+        match self.propagation {
+            Propagation::TwoArrays => self.two_array_streaming(),
+            Propagation::Swap => self.swap_streaming(),
+            Propagation::Fused => unreachable!(),
+        }
+    }
+
+    /// Population `n` streamed into the cell `c` from the populations `f`
+    #[inline(always)]
+    fn pull(&self, f: &[T], c: grid::Idx, n: P::Distribution) -> T {
+        // pull from the upstream neighbor, or let the boundary conditions
+        // provide it outside of non-periodic axes:
+        match self.grid.neighbor(c, n.opposite()) {
+            Some(cn) => f[self.f_idx(cn, n)],
+            None => boundary::missing(&f, |f, n| f[self.f_idx(c, n)], n),
+        }
+    }
+
+    /// Streaming into the second population array
+    fn two_array_streaming(&mut self) {
         let mut f_hlp =
             ::std::mem::replace(&mut self.f_hlp, Default::default());
-        f_hlp
-            .par_chunks_mut(P::Distribution::size())
-            .zip(self.grid.par_ids())
-            .for_each(|(f_hlp, c)| for n in P::Distribution::all() {
-                let cn = self.grid.neighbor(c, n);
-                f_hlp[n.value()] = *self.f_ref(cn, n);
-            });
+        self.par_store(&mut f_hlp, |c| {
+            let mut s = DistributionStorage::<P::Distribution, T>::default();
+            for n in P::Distribution::all() {
+                s.as_mut()[n.value()] = self.pull(&self.f, c, n);
+            }
+            Some(s)
+        });
         self.f_hlp = f_hlp;
     }
 
+    /// In-place streaming exchanging the populations crossing each link
+    fn swap_streaming(&mut self) {
+        use rayon::prelude::*;
+        let mut f = ::std::mem::replace(&mut self.f, Default::default());
+
+        // the slots of the populations entering from outside of
+        // non-periodic axes hold populations of their own cell, and are
+        // not part of any link:
+        self.par_update(&mut f, |c, f_c| {
+            let mut r = None;
+            for n in P::Distribution::all() {
+                if self.grid.neighbor(c, n.opposite()).is_some() {
+                    continue;
+                }
+                let f_n = boundary::missing(
+                    f_c,
+                    |s, n| s.as_ref()[n.opposite().value()],
+                    n,
+                );
+                r.get_or_insert_with(|| {
+                    let mut s =
+                        DistributionStorage::<P::Distribution, T>::default();
+                    s.as_mut().copy_from_slice(f_c.as_ref());
+                    s
+                }).as_mut()[n.value()] = f_n;
+            }
+            r
+        });
+
+        let p = SharedPopulations(f.as_mut_ptr());
+        self.grid.par_ids().for_each(|c| for n in P::Distribution::all() {
+            // each link is handled by its upstream cell along `n`:
+            if n.value() >= n.opposite().value() {
+                continue;
+            }
+            if let Some(cn) = self.grid.neighbor(c, n) {
+                // the two populations of a link are only accessed here
+                // because the links are symmetric (checked by
+                // `with_propagation`):
+                debug_assert!(
+                    cn.0 < self.grid.size() &&
+                        self.grid.neighbor(cn, n.opposite()) == Some(c)
+                );
+                let a = self.f_idx(c, n.opposite());
+                let b = self.f_idx(cn, n);
+                unsafe {
+                    ::std::ptr::swap(p.0.add(a), p.0.add(b));
+                }
+            }
+        });
+        self.f = f;
+    }
+
     /// Collision step
     fn collision(&mut self) {
         let mut f = ::std::mem::replace(&mut self.f, Default::default());
-        use rayon::prelude::*;
-        f.par_chunks_mut(P::Distribution::size())
-            .zip(self.f_hlp.par_chunks(P::Distribution::size()))
-            .zip(self.grid.par_ids())
-            .for_each(|((f, f_hlp), c)| {
+        let counts = if self.propagation == Propagation::Swap {
+            self.par_collide(
+                &mut f,
+                |c, f| {
+                    let mut s =
+                        DistributionStorage::<P::Distribution, T>::default();
+                    for n in P::Distribution::all() {
+                        s.as_mut()[n.value()] =
+                            unsafe { *f.add(self.f_idx(c, n)) };
+                    }
+                    s
+                },
+                |c, r| {
+                    if self.solid_boundary(c) {
+                        return None;
+                    }
+                    let mut s =
+                        DistributionStorage::<P::Distribution, T>::default();
+                    for n in P::Distribution::all() {
+                        s.as_mut()[n.opposite().value()] =
+                            r.as_ref()[n.value()];
+                    }
+                    Some(s)
+                },
+            )
+        } else {
+            self.par_collide(
+                &mut f,
+                |c, _| self.load(&self.f_hlp, c),
+                |c, r| if self.solid_boundary(c) { None } else { Some(r) },
+            )
+        };
+        self.counts.merge(counts);
+        self.f = f;
+    }
+
+    /// Fused pull streaming, collision, and boundary conditions step
+    ///
+    /// The populations of the previous iteration are kept in the second
+    /// array afterwards.
+    fn fused_step(&mut self) {
+        let mut f_new =
+            ::std::mem::replace(&mut self.f_hlp, Default::default());
+        let f = &self.f;
+        let counts = self.par_collide(
+            &mut f_new,
+            |c, _| {
                 if self.solid_boundary(c) {
-                    return;
+                    // solid cells are neither streamed into nor collided:
+                    return self.load(f, c);
                 }
-                let r = self.physics.collision(&f_hlp, |v, n| v[n.value()]);
-
+                let mut s =
+                    DistributionStorage::<P::Distribution, T>::default();
                 for n in P::Distribution::all() {
-                    f[n.value()] = r.as_ref()[n.value()];
+                    s.as_mut()[n.value()] = self.pull(f, c, n);
+                }
+                s
+            },
+            |c, mut r| {
+                if self.flags.flag(c) != boundary::Flag::Fluid {
+                    let conditions = self.flags.conditions(c);
+                    if let Some(b) = self.boundary_values(
+                        c,
+                        conditions,
+                        &r,
+                        |x, n| self.pull(f, self.grid.idx(x), n),
+                    ) {
+                        r = b;
+                    }
                 }
+                Some(r)
+            },
+        );
+        self.counts.merge(counts);
+        self.f_hlp = ::std::mem::replace(&mut self.f, f_new);
+    }
+
+    /// Streamed populations of the current iteration
+    ///
+    /// With `Propagation::Swap` they are only available until the
+    /// collision, and `Propagation::Fused` does not store them.
+    fn f_streamed(&self) -> &[T] {
+        match self.propagation {
+            Propagation::TwoArrays => &self.f_hlp,
+            Propagation::Swap => &self.f,
+            Propagation::Fused => unreachable!(),
+        }
+    }
+
+    /// Evaluates the populations of the cells of the boundary conditions
+    ///
+    /// The conditions read the streamed populations of all cells and the
+    /// post-collision populations of their own cell. With
+    /// `Propagation::Swap` they are evaluated before the collision, which
+    /// overwrites the streamed populations, and the post-collision
+    /// populations of a solid cell are its streamed ones.
+    fn evaluate_boundary_conditions(&mut self) {
+        use rayon::prelude::*;
+        let mut bc_values =
+            ::std::mem::replace(&mut self.bc_values, Vec::new());
+        let mut bc_streamed =
+            ::std::mem::replace(&mut self.bc_streamed, Vec::new());
+        let f_h = self.f_streamed();
+        let f: &[T] = match self.propagation {
+            Propagation::TwoArrays | Propagation::Fused => &self.f,
+            Propagation::Swap => f_h,
+        };
+        bc_values.resize_with(self.flags.len(), Default::default);
+        bc_values.par_iter_mut().enumerate().for_each(|(i, r)| {
+            let (c, conditions) = self.flags.cell(i);
+            let mut f_c = DistributionStorage::<P::Distribution, T>::default();
+            for n in P::Distribution::all() {
+                f_c.as_mut()[n.value()] = f[self.f_idx(c, n)];
+            }
+            if self.propagation == Propagation::Swap &&
+                !self.solid_boundary(c)
+            {
+                f_c = self.physics.collision(
+                    &f_c,
+                    |s, n| s.as_ref()[n.value()],
+                    self.grid.x(c),
+                    self.iteration,
+                );
+            }
+            *r = self.boundary_values(
+                c,
+                conditions,
+                &f_c,
+                |x, n| f_h[self.f_idx(self.grid.idx(x), n)],
+            );
+        });
+        if self.propagation == Propagation::Swap {
+            bc_streamed.resize_with(self.flags.len(), Default::default);
+            bc_streamed.par_iter_mut().enumerate().for_each(|(i, s)| {
+                *s = self.load(f_h, self.flags.cell(i).0);
             });
-        self.f = f;
+        }
+        self.bc_values = bc_values;
+        self.bc_streamed = bc_streamed;
+    }
+
+    /// Populations of the cell `c` set by the boundary conditions
+    /// `conditions` (if any) from its post-collision populations `f_c` and
+    /// the streamed populations `f_h(x, n)` of all cells
+    // not inlined into the loops over all cells, where it is seldom used:
+    #[inline(never)]
+    fn boundary_values<H>(
+        &self,
+        c: grid::Idx,
+        conditions: &[usize],
+        f_c: &DistributionStorage<P::Distribution, T>,
+        f_h: H,
+    ) -> Option<DistributionStorage<P::Distribution, T>>
+    where
+        H: Fn(grid::X, P::Distribution) -> T,
+    {
+        self.bcs.apply(
+            conditions,
+            f_c,
+            &f_h,
+            |s, n: P::Distribution| s.as_ref()[n.value()],
+            |f_h, x, n| f_h(x, n),
+            |x, n| {
+                let c = self.grid.idx(x);
+                self.grid.neighbor(c, n).map(|c| self.grid.x(c))
+            },
+            |x| self.flags.flag(self.grid.idx(x)),
+            |s| {
+                self.physics.collision(
+                    s,
+                    |s, n| s.as_ref()[n.value()],
+                    self.grid.x(c),
+                    self.iteration,
+                )
+            },
+            |rho, u| self.physics.equilibrium(rho, u),
+            self.grid.x(c),
+            self.iteration,
+        )
     }
 
     /// Applies boundary conditions
     fn apply_boundary_conditions(&mut self) {
-        let mut f = ::std::mem::replace(&mut self.f, Default::default());
+        // the conditions only read the post-collision populations of their
+        // own cell, so all of them are evaluated before any is written:
+        if self.propagation == Propagation::TwoArrays {
+            self.evaluate_boundary_conditions();
+        }
         use rayon::prelude::*;
-        f.par_chunks_mut(P::Distribution::size())
-            .zip(self.grid.par_ids())
-            .for_each(|(f, c)| {
-                let r = self.bcs.apply(
-                    &f,
-                    &self.f_hlp,
-                    |v, n: P::Distribution| v[n.value()],
-                    |v, n| v[Self::f_idx(c, n)],
-                    self.grid.x(c),
-                );
+        let mut f = ::std::mem::replace(&mut self.f, Default::default());
+        let p = SharedPopulations(f.as_mut_ptr());
+        self.bc_values.par_iter().enumerate().for_each(|(i, r)| {
+            if let Some(ref r) = *r {
+                // the boundary cells are distinct, so each population is
+                // written by a single thread:
+                let c = self.flags.cell(i).0;
+                for n in P::Distribution::all() {
+                    unsafe {
+                        *p.0.add(self.f_slot(c, n)) = r.as_ref()[n.value()];
+                    }
+                }
+            }
+        });
+        self.f = f;
+    }
 
-                if let Some(r) = r {
-                    for n in P::Distribution::all() {
-                        f[n.value()] = r.as_ref()[n.value()];
+    /// Momentum-exchange loads on the solid boundary conditions of the
+    /// current populations, with the torques about the point `p`.
+    ///
+    /// A boundary cell of overlapping conditions (e.g. a corner of two
+    /// walls) is set by the last of them, which therefore carries the loads
+    /// of all its links.
+    pub fn loads(&self, p: [f64; 3]) -> Vec<force::Load> {
+        let cell = |i| self.flags.cell(i).0;
+        let f_out = |i, n| self.f(cell(i), n);
+        match self.propagation {
+            Propagation::TwoArrays => self.link_loads(
+                p,
+                |i, n| self.f_hlp[self.f_idx(cell(i), n)],
+                f_out,
+            ),
+            // the streamed populations are pulled from the previous ones:
+            Propagation::Fused => self.link_loads(
+                p,
+                |i, n| self.pull(&self.f_hlp, cell(i), n),
+                f_out,
+            ),
+            // the streamed populations are recorded before the boundary
+            // conditions overwrite them (if any iteration was run):
+            Propagation::Swap => self.link_loads(
+                p,
+                |i, n| match self.bc_streamed.get(i) {
+                    Some(s) => s.as_ref()[n.value()],
+                    None => self.f(cell(i), n),
+                },
+                f_out,
+            ),
+        }
+    }
+
+    /// Momentum-exchange loads on the solid boundary conditions with the
+    /// torques about the point `p`.
+    ///
+    /// After the boundary conditions are applied, the solid cell `x_b`,
+    /// the `i`-th boundary cell, holds the populations `f_out(i, n)` leaving
+    /// the wall towards its fluid neighbors `x_b + c_n`, and the streamed
+    /// populations `f_in(i, -n)` arriving from them. The transferred
+    /// momentum acts at the wall half-way along the link. All links of a
+    /// cell of overlapping conditions load the last of them.
+    fn link_loads<FI, FO>(
+        &self,
+        p: [f64; 3],
+        f_in: FI,
+        f_out: FO,
+    ) -> Vec<force::Load>
+    where
+        FI: Fn(usize, P::Distribution) -> T + Sync,
+        FO: Fn(usize, P::Distribution) -> T + Sync,
+    {
+        use rayon::prelude::*;
+        let n_bcs = self.bcs.len();
+        (0..self.flags.len())
+            .into_par_iter()
+            .filter(|&i| self.solid_boundary(self.flags.cell(i).0))
+            .fold(|| vec![force::Load::default(); n_bcs], |mut loads, i| {
+                // the populations of a cell are set by the last of its
+                // conditions:
+                let (c, conditions) = self.flags.cell(i);
+                let x = self.grid.x(c);
+                let k = *conditions.last().unwrap();
+                for n in P::Distribution::all() {
+                    match self.grid.neighbor(c, n) {
+                        Some(c_f) if !self.solid_boundary(c_f) => {}
+                        _ => continue,
+                    }
+                    let f_io = (f_out(i, n) + f_in(i, n.opposite())).to_f64();
+                    let c_i = n.direction().num_array();
+                    let x_w = [x.0 as f64, x.1 as f64, x.2 as f64];
+                    let mut df = [0.; 3];
+                    let mut r = [0.; 3];
+                    for d in 0..3 {
+                        df[d] = -c_i[d] * f_io;
+                        r[d] = x_w[d] + 0.5 * c_i[d] - p[d];
                     }
+                    loads[k].add(df, r);
                 }
+                loads
+            })
+            .reduce(|| vec![force::Load::default(); n_bcs], |a, b| {
+                a.into_iter().zip(b).map(|(a, b)| a.sum(b)).collect()
+            })
+    }
+
+    /// Evaluates the loads of the monitor (if any)
+    fn monitor_forces(&mut self) {
+        let point = match self.forces {
+            Some(ref m) => m.point,
+            None => return,
+        };
+        let loads = if self.propagation == Propagation::Fused {
+            self.loads(point)
+        } else {
+            let f_h = self.f_streamed();
+            let bc_values = &self.bc_values;
+            self.link_loads(
+                point,
+                |i, n| f_h[self.f_idx(self.flags.cell(i).0, n)],
+                |i, n| bc_values[i].as_ref().unwrap().as_ref()[n.value()],
+            )
+        };
+        if let Some(ref mut m) = self.forces {
+            m.record(self.iteration, loads, G::dimension());
+        }
+    }
+
+    /// Streaming, collision, and boundary conditions steps as separate
+    /// passes over the populations
+    fn separate_passes(&mut self, write_output: bool) {
+        use time::Duration;
+        let d = Duration::span(|| self.streaming());
+        if write_output {
+            self.substep("propagation", d);
+        }
+
+        // the in-place collision overwrites the streamed populations
+        // that the boundary conditions and loads depend on:
+        let swap = self.propagation == Propagation::Swap;
+        if swap {
+            let d = Duration::span(|| {
+                self.evaluate_boundary_conditions();
+                self.monitor_forces();
             });
-        self.f = f;
+            if write_output {
+                self.substep("bcs evaluation", d);
+            }
+        }
+
+        let d = Duration::span(|| self.collision());
+        if write_output {
+            self.substep("collision", d);
+        }
+
+        let d = Duration::span(|| self.apply_boundary_conditions());
+        if write_output {
+            self.substep("bcs", d);
+        }
     }
 
     /// Executes `n_it` iterations writing output every `n_out` iterations.
@@ -133,20 +856,22 @@ impl<P: ::Physics> Solver<P> {
         loop {
             let write_output = n_out > 0 && iter % n_out == 0;
             let d = Duration::span(|| {
-                let d = Duration::span(|| self.streaming());
-                if write_output {
-                    self.substep("propagation", d);
-                }
-
-                let d = Duration::span(|| self.collision());
-                if write_output {
-                    self.substep("collision", d);
+                if self.propagation == Propagation::Fused {
+                    let d = Duration::span(|| self.fused_step());
+                    if write_output {
+                        self.substep("stream-collide-bcs", d);
+                    }
+                } else {
+                    self.separate_passes(write_output);
                 }
 
-                let d = Duration::span(|| self.apply_boundary_conditions());
-                if write_output {
-                    self.substep("bcs", d);
+                if self.propagation != Propagation::Swap {
+                    let d = Duration::span(|| self.monitor_forces());
+                    if write_output && self.forces.is_some() {
+                        self.substep("forces", d);
+                    }
                 }
+                self.iteration += 1;
 
                 n_it -= 1;
                 if write_output {
@@ -165,7 +890,7 @@ impl<P: ::Physics> Solver<P> {
     }
 
     /// Integrates the distribution functions over the volume
-    fn integral(&self) -> num {
+    fn integral(&self) -> T {
         use rayon::prelude::*;
         self.grid
             .par_ids()
@@ -174,14 +899,19 @@ impl<P: ::Physics> Solver<P> {
     }
 
     /// Prints line info of a whole iteration step
-    fn step(&self, n_it: usize, duration: time::Duration) {
+    fn step(&mut self, n_it: usize, duration: time::Duration) {
         let integral = self.integral();
-        println!(
+        print!(
             "#{} | integral: {} | duration: {} ms",
             n_it,
             integral,
             duration.num_milliseconds()
         );
+        let counts = ::std::mem::replace(&mut self.counts, Default::default());
+        match self.physics.statistics(counts) {
+            Some(s) => println!(" | {}", s),
+            None => println!(),
+        }
     }
     /// Prints line info of an iteration sub-step
     fn substep(&self, name: &str, duration: time::Duration) {
@@ -197,14 +927,16 @@ impl<P: ::Physics> Solver<P> {
     /// Writes the solution to a VTK file.
     fn write_vtk(&self, n_it: usize) {
         let fname = format!("lbm_rs_output_{}", n_it);
-        let mut vtk_writer = vtk::write_vtk(&fname, self.grid);
+        let mut vtk_writer = vtk::write_vtk::<T, _>(&fname, self.grid);
         vtk_writer = self.physics.write(
             vtk_writer,
+            self.iteration - 1,
+            |c| self.grid.x(c),
             |c| self.solid_boundary(c),
             |c, n| *self.f_ref(c, n),
         );
         vtk_writer.write_scalar("boundary_idx", |c| {
-            self.bcs.idx(self.grid.x(c)).map_or(-1 as i32, |v| v as i32)
+            self.flags.flag(c).idx().map_or(-1 as i32, |v| v as i32)
         });
     }
 }
@@ -1,4 +1,4 @@
-use num;
+use Float;
 use geometry;
 use io::vtk;
 use grid;
@@ -6,74 +6,223 @@ use grid;
 /// TODO: move traits to appropriate modules
 
 pub trait Distribution: Sized + Copy + Sync + Send {
-    type Storage: AsRef<[num]> + AsMut<[num]> + Default;
+    /// Populations of a cell
+    type Storage<T: Float>: AsRef<[T]> + AsMut<[T]> + Default + Send + Sync;
     type AllIterator: Iterator<Item = Self>;
-    #[inline(always)]
     fn all() -> Self::AllIterator;
-    #[inline(always)]
-    fn c_squ() -> num;
-    #[inline(always)]
+    fn c_squ() -> f64;
+    /// Number of spatial dimensions of the lattice
+    fn dimension() -> usize;
     fn direction(&self) -> geometry::Direction;
-    #[inline(always)]
     fn from_direction(geometry::Direction) -> Option<Self>;
-    #[inline(always)]
-    fn constant(&self) -> num;
-    #[inline(always)]
+    fn constant(&self) -> f64;
     fn size() -> usize;
-    #[inline(always)]
     fn value(&self) -> usize;
-    #[inline(always)]
     fn center() -> Self;
-    #[inline(always)]
     fn opposite(&self) -> Self;
 }
 
-pub type DistributionStorage<D> = <D as Distribution>::Storage;
+pub type DistributionStorage<D, T = f64> = <D as Distribution>::Storage<T>;
+
+/// Number of cells collided at once by `Physics::collision_lanes`
+pub const LANES: usize = 8;
+
+/// Largest number of populations of a cell, `Distribution::size()` of
+/// D3Q27
+pub const MAX_SIZE: usize = 27;
+
+/// A population of `LANES` cells
+pub type Lanes<T = f64> = [T; LANES];
+
+/// Counters of the collisions of a set of cells (e.g. of a block of
+/// `LANES` cells), merged by the solver.
+pub trait Counts: Copy + Default + Send + Sync {
+    /// Adds the counters of `other`
+    fn merge(&mut self, other: Self);
+}
 
+impl Counts for () {
+    #[inline(always)]
+    fn merge(&mut self, _: ()) {}
+}
+
+/// Collides the populations `f[n.value()][k]` of the cells `k < len` one
+/// cell at a time with `col`.
+#[inline(always)]
+pub fn scalar_collision_lanes<T, D, C>(
+    col: &C,
+    f: &mut [Lanes<T>],
+    len: usize,
+    counts: &mut C::Counts,
+) where
+    T: Float,
+    D: Distribution,
+    C: Collision<D>,
+{
+    for k in 0..len {
+        let r = col.counted_collision(&f, |f, n: D| f[n.value()][k], counts);
+        for n in D::all() {
+            f[n.value()][k] = r.as_ref()[n.value()];
+        }
+    }
+}
+
+/// Axis-aligned lattice links.
 pub trait DirectDistribution: Distribution {
     type DirectIterator: Iterator<Item = Self>;
-    #[inline(always)]
     fn direct() -> Self::DirectIterator;
 }
 
+/// Non-axis-aligned lattice links (in 3D: both edge and corner diagonals).
 pub trait DiagonalDistribution: Distribution {
     type DiagonalIterator: Iterator<Item = Self>;
-    #[inline(always)]
     fn diagonal() -> Self::DiagonalIterator;
 }
 
 
 pub trait Collision<D: Distribution>: Copy + Sync + Send {
+    /// Counters of the collisions (if any)
+    type Counts: Counts = ();
+
+    fn collision<T, H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T;
+
+    /// Collision counted in `counts`
+    #[inline(always)]
+    fn counted_collision<T, H, IH>(
+        &self,
+        f_hlp: &H,
+        idx_h: IH,
+        _counts: &mut Self::Counts,
+    ) -> D::Storage<T>
+    where
+        T: Float,
+        IH: Fn(&H, D) -> T,
+    {
+        self.collision(f_hlp, idx_h)
+    }
+
+    /// Collides the populations `f[n.value()][k]` of the cells `k < len`
+    /// in place counting them in `counts`.
     #[inline(always)]
-    fn collision<H, IH>(&self, f_hlp: &H, idx_h: IH) -> D::Storage
+    fn collision_lanes<T: Float>(
+        &self,
+        f: &mut [Lanes<T>],
+        len: usize,
+        counts: &mut Self::Counts,
+    ) {
+        scalar_collision_lanes(self, f, len, counts);
+    }
+
+    /// Statistics of the collisions counted in `counts` (if any)
+    fn statistics(&self, _counts: Self::Counts) -> Option<String> {
+        None
+    }
+
+    /// Writes collision specific cell data
+    fn write<T, O, F>(
+        &self,
+        vtk_writer: vtk::CellDataWriter,
+        _: O,
+        _: F,
+    ) -> vtk::CellDataWriter
     where
-        IH: Fn(&H, D) -> num;
+        T: Float,
+        F: Fn(grid::Idx, D) -> T,
+        O: Fn(grid::Idx) -> bool,
+    {
+        vtk_writer
+    }
 }
 
 
 pub trait Physics: Copy + Sync + Send {
     type Distribution: Distribution;
-    #[inline(always)]
-    fn collision<FH, IFH>(
+    /// Counters of the collisions (if any)
+    type Counts: Counts = ();
+    /// Collides the populations of the cell at `x` during the iteration
+    /// `n_it`.
+    fn collision<T, FH, IFH>(
         &self,
         f_h: &FH,
         idx_f_h: IFH,
-    ) -> DistributionStorage<Self::Distribution>
+        x: grid::X,
+        n_it: usize,
+    ) -> DistributionStorage<Self::Distribution, T>
     where
-        IFH: Fn(&FH, Self::Distribution) -> num;
+        T: Float,
+        IFH: Fn(&FH, Self::Distribution) -> T;
+    /// Collides the populations `f[n.value()][k]` of the cells at `x[k]`
+    /// during the iteration `n_it` in place counting them in `counts`.
     #[inline(always)]
-    fn integral<F: Fn(Self::Distribution) -> num>(_: F) -> num {
-        0.0
+    fn collision_lanes<T: Float>(
+        &self,
+        f: &mut [Lanes<T>],
+        x: &[grid::X],
+        n_it: usize,
+        _counts: &mut Self::Counts,
+    ) {
+        for (k, &x) in x.iter().enumerate() {
+            let r = self.collision(
+                &f,
+                |f, n: Self::Distribution| f[n.value()][k],
+                x,
+                n_it,
+            );
+            for n in Self::Distribution::all() {
+                f[n.value()][k] = r.as_ref()[n.value()];
+            }
+        }
+    }
+    /// Equilibrium populations of the density `rho` and the velocity `u`
+    ///
+    /// Defaults to the second-order expansion of the Maxwell-Boltzmann
+    /// distribution.
+    #[inline(always)]
+    fn equilibrium<T: Float>(
+        &self,
+        rho: T,
+        u: [T; 3],
+    ) -> DistributionStorage<Self::Distribution, T> {
+        let t = T::from_f64;
+        let c_squ = t(Self::Distribution::c_squ());
+        let u_squ = u[0] * u[0] + u[1] * u[1] + u[2] * u[2];
+        let mut r = DistributionStorage::<Self::Distribution, T>::default();
+        for n in Self::Distribution::all() {
+            let c = n.direction().num_array();
+            let c_u = t(c[0]) * u[0] + t(c[1]) * u[1] + t(c[2]) * u[2];
+            r.as_mut()[n.value()] = t(n.constant()) * rho *
+                (t(1.) + c_u / c_squ + c_u * c_u / (t(2.) * c_squ * c_squ) -
+                    u_squ / (t(2.) * c_squ));
+        }
+        r
+    }
+    #[inline(always)]
+    fn integral<T: Float, F: Fn(Self::Distribution) -> T>(_: F) -> T {
+        T::default()
+    }
+
+    /// Statistics of the collisions counted in `counts` (if any)
+    fn statistics(&self, _counts: Self::Counts) -> Option<String> {
+        None
     }
 
-    fn write<O, F>(
+    /// Writes the cell data of the populations collided during the
+    /// iteration `n_it`
+    fn write<T, X, O, F>(
         &self,
         vtk_writer: vtk::CellDataWriter,
+        _n_it: usize,
+        _: X,
         _: O,
         _: F,
     ) -> vtk::CellDataWriter
     where
-        F: Fn(grid::Idx, Self::Distribution) -> num,
+        T: Float,
+        X: Fn(grid::Idx) -> grid::X,
+        F: Fn(grid::Idx, Self::Distribution) -> T,
         O: Fn(grid::Idx) -> bool,
     {
         vtk_writer